@@ -0,0 +1,24 @@
+-- Socios activos: solo a ellos se les genera la cuota mensual
+alter table members add column is_active boolean not null default true;
+
+-- Una cuota impaga todavía no tiene fecha de pago
+alter table dues alter column payment_date drop not null;
+alter table dues add column amount_paid decimal(10, 2) not null default 0;
+
+update dues set amount_paid = amount where is_payed;
+update dues set payment_date = null where not is_payed;
+
+-- Una sola cuota por socio y mes, para que la facturación sea idempotente
+alter table dues add constraint dues_member_month_year_key unique (member_id, month, year);
+
+create table dues_payments (
+    id uuid primary key default uuid_generate_v4(),
+    due_id uuid not null references dues(id),
+    amount decimal(10, 2) not null check (amount > 0),
+    payment_date date not null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp
+);
+
+insert into dues_payments (due_id, amount, payment_date)
+select id, amount_paid, payment_date from dues where is_payed;
@@ -0,0 +1,12 @@
+-- Las actividades dadas de baja se conservan para el historial
+alter table activities add column retired_at timestamp;
+
+-- Quitar inscripciones duplicadas antes de exigir unicidad
+delete from members_activities a
+using members_activities b
+where a.member_id = b.member_id
+  and a.activity_id = b.activity_id
+  and (a.created_at, a.id) > (b.created_at, b.id);
+
+alter table members_activities
+    add constraint members_activities_member_activity_key unique (member_id, activity_id);
@@ -0,0 +1,10 @@
+-- Días de la semana como enum en lugar de texto libre ('Lunes', 'Miércoles')
+create type weekday as enum ('lunes', 'martes', 'miercoles', 'jueves', 'viernes', 'sabado', 'domingo');
+
+alter table activities_schedule
+    alter column day type weekday using translate(lower(trim(day)), 'áéíóú', 'aeiou')::weekday;
+
+alter table activities_schedule
+    add constraint activities_schedule_time_range_check check (end_time > start_time);
+
+create index activities_schedule_space_day_idx on activities_schedule (space_id, day);
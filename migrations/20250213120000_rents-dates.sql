@@ -0,0 +1,16 @@
+-- Los alquileres pasan a tener un día concreto
+alter table rents add column rent_date date;
+update rents set rent_date = payment_date;
+alter table rents alter column rent_date set not null;
+
+-- Un alquiler impago todavía no tiene fecha de pago
+alter table rents alter column payment_date drop not null;
+update rents set payment_date = null where not is_payed;
+
+alter table rents add column cancelled_at timestamp;
+alter table rents add constraint rents_time_range_check check (end_time > start_time);
+
+create index rents_space_date_idx on rents (space_id, rent_date);
+
+-- Tarifa por hora usada para cotizar alquileres
+alter table space add column hourly_price decimal(10, 2);
@@ -0,0 +1,23 @@
+alter table space add column capacity int check (capacity > 0);
+alter table space add column is_indoor boolean not null default true;
+alter table space add column retired_at timestamp;
+
+update space set is_indoor = false where name in ('Cancha Exterior', 'Parrillero');
+
+-- Un día sin fila significa que el espacio está cerrado ese día
+create table space_opening_hours (
+    id uuid primary key default uuid_generate_v4(),
+    space_id uuid not null references space(id),
+    day weekday not null,
+    opens_at time not null,
+    closes_at time not null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp,
+    constraint space_opening_hours_space_day_key unique (space_id, day),
+    constraint space_opening_hours_time_range_check check (closes_at > opens_at)
+);
+
+insert into space_opening_hours (space_id, day, opens_at, closes_at)
+select s.id, d.day, '08:00', '23:00'
+from space s
+cross join unnest(enum_range(null::weekday)) as d(day);
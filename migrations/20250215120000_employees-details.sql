@@ -0,0 +1,6 @@
+alter table employees add column position varchar(255) not null default 'Sin asignar';
+alter table employees add column hire_date date not null default current_date;
+alter table employees add column email varchar(255);
+alter table employees add column is_active boolean not null default true;
+
+alter table employees alter column position drop default;
@@ -0,0 +1,10 @@
+-- Sueldo mensual con el que se generan los pagos de cada funcionario
+alter table employees add column salary decimal(10, 2) check (salary > 0);
+
+-- Un pago pendiente todavía no tiene fecha
+alter table employees_payments alter column payment_date drop not null;
+update employees_payments set payment_date = null where not is_payed;
+
+-- Un solo pago por funcionario y mes, para que la liquidación sea idempotente
+alter table employees_payments
+    add constraint employees_payments_employee_month_year_key unique (employee_id, month, year);
@@ -0,0 +1,9 @@
+-- Registro de cada consulta a la ficha de emergencia de un socio
+create table emergency_card_accesses (
+    id uuid primary key default uuid_generate_v4(),
+    member_id uuid not null references members(id),
+    accessed_by varchar(255) not null,
+    accessed_at timestamp not null default current_timestamp
+);
+
+create index emergency_card_accesses_member_idx on emergency_card_accesses (member_id, accessed_at);
@@ -0,0 +1,13 @@
+-- Tokens de refresco: se guarda solo el hash y se rotan en cada uso
+create table refresh_tokens (
+    id uuid primary key default uuid_generate_v4(),
+    user_id uuid not null references users(id),
+    token_hash varchar(255) not null unique,
+    expires_at timestamp not null,
+    revoked_at timestamp,
+    replaced_by uuid references refresh_tokens(id),
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp
+);
+
+create index refresh_tokens_user_idx on refresh_tokens (user_id);
@@ -0,0 +1,84 @@
+-- Roles y permisos: cada ruta exige un permiso y cada rol agrupa permisos
+create table roles (
+    name varchar(255) primary key,
+    description varchar(255) not null,
+    created_at timestamp not null default current_timestamp,
+    updated_at timestamp not null default current_timestamp
+);
+
+create table permissions (
+    name varchar(255) primary key,
+    description varchar(255) not null
+);
+
+create table role_permissions (
+    role_name varchar(255) not null references roles(name) on update cascade on delete cascade,
+    permission_name varchar(255) not null references permissions(name) on update cascade on delete cascade,
+    primary key (role_name, permission_name)
+);
+
+INSERT INTO roles (name, description) VALUES
+('admin', 'Administrador'),
+('treasurer', 'Tesorería'),
+('receptionist', 'Recepción'),
+('coach', 'Profesor'),
+('user', 'Usuario sin permisos asignados');
+
+INSERT INTO permissions (name, description) VALUES
+('members:read', 'Consultar socios'),
+('members:write', 'Crear, modificar y dar de baja socios'),
+('members:emergency_card', 'Consultar la ficha de emergencia de un socio'),
+('dues:read', 'Consultar cuotas y morosidad'),
+('dues:collect', 'Registrar pagos de cuotas'),
+('dues:bill', 'Generar la facturación mensual de cuotas'),
+('activities:read', 'Consultar inscriptos en actividades'),
+('activities:write', 'Crear, modificar y retirar actividades'),
+('activities:enroll', 'Inscribir y desinscribir socios en actividades'),
+('schedule:write', 'Modificar el horario semanal'),
+('spaces:write', 'Crear, modificar y retirar espacios'),
+('rents:read', 'Consultar alquileres'),
+('rents:write', 'Crear y cancelar alquileres'),
+('rents:collect', 'Registrar pagos de alquileres'),
+('employees:read', 'Consultar funcionarios'),
+('employees:write', 'Crear, modificar y dar de baja funcionarios'),
+('payroll:read', 'Consultar pagos de sueldos'),
+('payroll:run', 'Generar y pagar la liquidación de sueldos'),
+('medical_societies:read', 'Consultar sociedades médicas y cobertura'),
+('medical_societies:write', 'Crear, modificar y eliminar sociedades médicas'),
+('users:manage', 'Administrar usuarios y roles');
+
+INSERT INTO role_permissions (role_name, permission_name)
+SELECT 'admin', name FROM permissions;
+
+INSERT INTO role_permissions (role_name, permission_name) VALUES
+('treasurer', 'members:read'),
+('treasurer', 'dues:read'),
+('treasurer', 'dues:collect'),
+('treasurer', 'dues:bill'),
+('treasurer', 'rents:read'),
+('treasurer', 'rents:collect'),
+('treasurer', 'employees:read'),
+('treasurer', 'payroll:read'),
+('treasurer', 'payroll:run'),
+('treasurer', 'medical_societies:read'),
+('receptionist', 'members:read'),
+('receptionist', 'members:write'),
+('receptionist', 'members:emergency_card'),
+('receptionist', 'dues:read'),
+('receptionist', 'dues:collect'),
+('receptionist', 'activities:read'),
+('receptionist', 'activities:enroll'),
+('receptionist', 'rents:read'),
+('receptionist', 'rents:write'),
+('receptionist', 'rents:collect'),
+('receptionist', 'medical_societies:read'),
+('coach', 'members:read'),
+('coach', 'members:emergency_card'),
+('coach', 'activities:read');
+
+-- Normalizar los roles cargados en el seed
+UPDATE users SET rolename = 'admin' WHERE rolename = 'Administrador';
+UPDATE users SET rolename = 'user' WHERE rolename NOT IN (SELECT name FROM roles);
+
+ALTER TABLE users
+    ADD CONSTRAINT users_rolename_fkey FOREIGN KEY (rolename) REFERENCES roles(name) ON UPDATE CASCADE;
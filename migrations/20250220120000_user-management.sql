@@ -0,0 +1,14 @@
+-- Usuarios deshabilitados y tokens de un solo uso para definir la contraseña
+ALTER TABLE users ADD COLUMN disabled_at timestamp;
+
+create table password_tokens (
+    id uuid primary key default uuid_generate_v4(),
+    user_id uuid not null references users(id),
+    token_hash varchar(255) not null unique,
+    purpose varchar(20) not null check (purpose in ('invite', 'reset')),
+    expires_at timestamp not null,
+    used_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index password_tokens_user_idx on password_tokens (user_id);
@@ -0,0 +1,23 @@
+-- Intentos fallidos de inicio de sesión por correo y por IP, y registro de bloqueos
+create table login_attempts (
+    id uuid primary key default uuid_generate_v4(),
+    kind varchar(10) not null check (kind in ('email', 'ip')),
+    key varchar(255) not null,
+    failed_count integer not null default 0,
+    last_failed_at timestamp not null default current_timestamp,
+    locked_until timestamp,
+    unique (kind, key)
+);
+
+create table login_lockouts (
+    id uuid primary key default uuid_generate_v4(),
+    kind varchar(10) not null,
+    key varchar(255) not null,
+    failed_count integer not null,
+    locked_until timestamp not null,
+    cleared_at timestamp,
+    cleared_by varchar(255),
+    created_at timestamp not null default current_timestamp
+);
+
+create index login_lockouts_key_idx on login_lockouts (kind, key);
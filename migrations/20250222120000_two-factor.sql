@@ -0,0 +1,30 @@
+-- Verificación en dos pasos (TOTP) con códigos de recuperación
+ALTER TABLE users
+    ADD COLUMN totp_secret varchar(255),
+    ADD COLUMN totp_enabled_at timestamp;
+
+ALTER TABLE roles ADD COLUMN require_two_factor boolean not null default false;
+
+-- Los roles con acceso a datos médicos y a dinero la exigen desde el inicio
+UPDATE roles SET require_two_factor = true WHERE name IN ('admin', 'treasurer');
+
+create table recovery_codes (
+    id uuid primary key default uuid_generate_v4(),
+    user_id uuid not null references users(id),
+    code_hash varchar(255) not null unique,
+    used_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
+
+create index recovery_codes_user_idx on recovery_codes (user_id);
+
+-- Segundo paso pendiente de un inicio de sesión cuya contraseña ya fue verificada
+create table two_factor_challenges (
+    id uuid primary key default uuid_generate_v4(),
+    user_id uuid not null references users(id),
+    token_hash varchar(255) not null unique,
+    failed_attempts integer not null default 0,
+    expires_at timestamp not null,
+    used_at timestamp,
+    created_at timestamp not null default current_timestamp
+);
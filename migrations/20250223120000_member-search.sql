@@ -0,0 +1,19 @@
+-- Búsqueda de socios sin distinguir tildes ni mayúsculas y tolerante a errores de tipeo
+create extension if not exists unaccent;
+create extension if not exists pg_trgm;
+
+-- unaccent no es IMMUTABLE; fijando el diccionario se puede usar en índices
+create or replace function immutable_unaccent(text) returns text as $$
+    select public.unaccent('public.unaccent'::regdictionary, $1)
+$$ language sql immutable parallel safe strict;
+
+create or replace function member_search_name(name text, lastname text) returns text as $$
+    select lower(immutable_unaccent(name || ' ' || lastname))
+$$ language sql immutable parallel safe strict;
+
+create index members_search_name_idx on members
+    using gin (member_search_name(name, lastname) gin_trgm_ops);
+create index members_ci_digits_idx on members
+    using gin (regexp_replace(ci, '\D', '', 'g') gin_trgm_ops);
+create index members_phone_digits_idx on members
+    using gin (regexp_replace(phone, '\D', '', 'g') gin_trgm_ops);
@@ -0,0 +1,24 @@
+-- La cédula se guarda normalizada (solo dígitos) y no puede repetirse
+UPDATE members SET ci = regexp_replace(ci, '[^0-9]', '', 'g') WHERE ci ~ '[^0-9]';
+UPDATE employees SET ci = regexp_replace(ci, '[^0-9]', '', 'g') WHERE ci ~ '[^0-9]';
+
+-- Si hay duplicados se aborta con un mensaje claro: hay que unificarlos a mano antes de migrar
+DO $$
+DECLARE
+    duplicated text;
+BEGIN
+    SELECT string_agg(ci, ', ') INTO duplicated
+    FROM (SELECT ci FROM members GROUP BY ci HAVING COUNT(*) > 1) d;
+    IF duplicated IS NOT NULL THEN
+        RAISE EXCEPTION 'Socios con cédula repetida: %', duplicated;
+    END IF;
+
+    SELECT string_agg(ci, ', ') INTO duplicated
+    FROM (SELECT ci FROM employees GROUP BY ci HAVING COUNT(*) > 1) d;
+    IF duplicated IS NOT NULL THEN
+        RAISE EXCEPTION 'Funcionarios con cédula repetida: %', duplicated;
+    END IF;
+END $$;
+
+ALTER TABLE members ADD CONSTRAINT members_ci_key UNIQUE (ci);
+ALTER TABLE employees ADD CONSTRAINT employees_ci_key UNIQUE (ci);
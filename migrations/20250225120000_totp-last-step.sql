@@ -0,0 +1,2 @@
+-- Último intervalo TOTP aceptado por usuario, para no aceptar dos veces el mismo código
+alter table users add column totp_last_step bigint;
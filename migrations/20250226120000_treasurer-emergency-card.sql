@@ -0,0 +1,4 @@
+-- La ficha de emergencia tiene que estar al alcance de cualquier rol del personal
+INSERT INTO role_permissions (role_name, permission_name) VALUES
+('treasurer', 'members:emergency_card')
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,5 @@
+-- Los pedidos de restablecimiento de contraseña se limitan con la misma tabla que los inicios de sesión
+ALTER TABLE login_attempts ALTER COLUMN kind TYPE varchar(20);
+ALTER TABLE login_attempts DROP CONSTRAINT login_attempts_kind_check;
+ALTER TABLE login_attempts ADD CONSTRAINT login_attempts_kind_check
+    CHECK (kind IN ('email', 'ip', 'reset_email', 'reset_ip'));
@@ -0,0 +1,3 @@
+-- La cédula ya se guarda solo con dígitos: se busca e indexa la columna directamente
+drop index if exists members_ci_digits_idx;
+create index members_ci_trgm_idx on members using gin (ci gin_trgm_ops);
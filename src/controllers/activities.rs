@@ -0,0 +1,193 @@
+use axum::extract::State;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    helpers::extract::{Json, Path, Query},
+    models::activity::{Activity, ActivityMember},
+    repository::activity::ActivityRepository,
+    utils::AppState,
+};
+
+use super::users::ApiResponse;
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityCreateRequest {
+    pub name: String,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityUpdateRequest {
+    pub name: Option<String>,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityListQuery {
+    #[serde(default)]
+    pub include_retired: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnrollRequest {
+    pub member_id: Uuid,
+}
+
+pub async fn find_all(
+    State(state): State<AppState>,
+    Query(query): Query<ActivityListQuery>,
+) -> Result<Json<ApiResponse<Vec<Activity>>>, AppError> {
+    let activities = ActivityRepository::find_all(&state.pool, query.include_retired).await?;
+    Ok(Json(ApiResponse::new(activities)))
+}
+
+pub async fn find_one(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Activity>>, AppError> {
+    let activity = ActivityRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Actividad no encontrada"))?;
+    Ok(Json(ApiResponse::new(activity)))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(body): Json<ActivityCreateRequest>,
+) -> Result<Json<ApiResponse<Activity>>, AppError> {
+    let name = body.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest(
+            "El nombre de la actividad es obligatorio".to_string(),
+        ));
+    }
+    if ActivityRepository::find_by_name(&state.pool, name)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict(format!(
+            "La actividad \"{}\" ya existe",
+            name
+        )));
+    }
+
+    let category = body
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty());
+    let activity = ActivityRepository::create(&state.pool, name, category).await?;
+    Ok(Json(ApiResponse::new(activity)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<ActivityUpdateRequest>,
+) -> Result<Json<ApiResponse<Activity>>, AppError> {
+    let mut activity = ActivityRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Actividad no encontrada"))?;
+
+    if let Some(name) = body.name {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::BadRequest(
+                "El nombre de la actividad es obligatorio".to_string(),
+            ));
+        }
+        if let Some(existing) = ActivityRepository::find_by_name(&state.pool, name).await? {
+            if existing.id != activity.id {
+                return Err(AppError::Conflict(format!(
+                    "La actividad \"{}\" ya existe",
+                    name
+                )));
+            }
+        }
+        activity.name = name.to_string();
+    }
+    if let Some(category) = body.category {
+        let category = category.trim();
+        activity.category = (!category.is_empty()).then(|| category.to_string());
+    }
+
+    let activity = ActivityRepository::update(&state.pool, &activity).await?;
+    Ok(Json(ApiResponse::new(activity)))
+}
+
+pub async fn retire(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Activity>>, AppError> {
+    let activity = ActivityRepository::retire(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Actividad no encontrada"))?;
+    Ok(Json(ApiResponse::new(activity)))
+}
+
+pub async fn find_roster(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<ActivityMember>>>, AppError> {
+    ActivityRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Actividad no encontrada"))?;
+    let roster = ActivityRepository::find_roster(&state.pool, uuid).await?;
+    Ok(Json(ApiResponse::new(roster)))
+}
+
+pub async fn enroll(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<EnrollRequest>,
+) -> Result<Json<ApiResponse<Vec<ActivityMember>>>, AppError> {
+    let activity = ActivityRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Actividad no encontrada"))?;
+    if activity.retired_at.is_some() {
+        return Err(AppError::BadRequest(format!(
+            "La actividad \"{}\" fue dada de baja",
+            activity.name
+        )));
+    }
+
+    ActivityRepository::enroll(&state.pool, uuid, body.member_id)
+        .await
+        .map_err(|e| {
+            AppError::from(e)
+                .on_unique_violation(&format!(
+                    "El socio ya está inscripto en \"{}\"",
+                    activity.name
+                ))
+                .on_invalid_reference("El socio indicado no existe")
+        })?;
+
+    let roster = ActivityRepository::find_roster(&state.pool, uuid).await?;
+    Ok(Json(ApiResponse::new(roster)))
+}
+
+pub async fn unenroll(
+    State(state): State<AppState>,
+    Path((uuid, member_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ApiResponse<Vec<ActivityMember>>>, AppError> {
+    ActivityRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Actividad no encontrada"))?;
+    if !ActivityRepository::unenroll(&state.pool, uuid, member_id).await? {
+        return Err(AppError::NotFound(
+            "El socio no está inscripto en la actividad",
+        ));
+    }
+    let roster = ActivityRepository::find_roster(&state.pool, uuid).await?;
+    Ok(Json(ApiResponse::new(roster)))
+}
+
+pub async fn find_by_member(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<Activity>>>, AppError> {
+    let activities = ActivityRepository::find_by_member(&state.pool, uuid).await?;
+    Ok(Json(ApiResponse::new(activities)))
+}
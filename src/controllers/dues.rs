@@ -0,0 +1,213 @@
+use axum::{extract::State, response::Response};
+use chrono::NaiveDate;
+use futures_util::TryStreamExt;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, FieldError},
+    helpers::{
+        dates,
+        extract::{Json, Path, Query},
+        spreadsheet::{self, ExportQuery},
+    },
+    models::due::{Arrears, Due, DueListItem, DuePayment, UnpaidDue},
+    repository::due::{DueFilter, DueRepository},
+    utils::AppState,
+};
+
+use super::users::ApiResponse;
+
+#[derive(Debug, Deserialize)]
+pub struct BillingRunRequest {
+    pub month: i32,
+    pub year: i32,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BillingRunResponse {
+    pub month: i32,
+    pub year: i32,
+    pub created: usize,
+    pub dues: Vec<Due>,
+}
+
+pub async fn billing_run(
+    State(state): State<AppState>,
+    Json(body): Json<BillingRunRequest>,
+) -> Result<Json<ApiResponse<BillingRunResponse>>, AppError> {
+    if !(1..=12).contains(&body.month) {
+        return Err(AppError::BadRequest(
+            "El mes debe estar entre 1 y 12".to_string(),
+        ));
+    }
+    if !(2000..=2100).contains(&body.year) {
+        return Err(AppError::BadRequest(format!("Año inválido: {}", body.year)));
+    }
+    if body.amount <= Decimal::ZERO {
+        return Err(AppError::BadRequest(
+            "El monto de la cuota debe ser mayor a cero".to_string(),
+        ));
+    }
+
+    let dues = DueRepository::billing_run(&state.pool, body.month, body.year, body.amount).await?;
+    Ok(Json(ApiResponse::new(BillingRunResponse {
+        month: body.month,
+        year: body.year,
+        created: dues.len(),
+        dues,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PaymentRequest {
+    pub amount: Decimal,
+    pub payment_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PaymentResponse {
+    pub due: Due,
+    pub payment: DuePayment,
+}
+
+pub async fn register_payment(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<PaymentRequest>,
+) -> Result<Json<ApiResponse<PaymentResponse>>, AppError> {
+    if body.amount <= Decimal::ZERO {
+        return Err(AppError::BadRequest(
+            "El monto del pago debe ser mayor a cero".to_string(),
+        ));
+    }
+    let payment_date = body.payment_date.unwrap_or_else(dates::today);
+    if payment_date > dates::today() {
+        return Err(AppError::BadRequest(
+            "La fecha de pago no puede ser futura".to_string(),
+        ));
+    }
+
+    let mut tx = state.pool.begin().await?;
+    let due = DueRepository::find_for_update(&mut tx, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Cuota no encontrada"))?;
+
+    let remaining = due.amount - due.amount_paid;
+    if due.is_payed || remaining <= Decimal::ZERO {
+        return Err(AppError::BadRequest("La cuota ya está paga".to_string()));
+    }
+    if body.amount > remaining {
+        return Err(AppError::BadRequest(format!(
+            "El pago supera el saldo pendiente de la cuota ({})",
+            remaining
+        )));
+    }
+
+    let (due, payment) =
+        DueRepository::add_payment(&mut tx, uuid, body.amount, payment_date).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::new(PaymentResponse { due, payment })))
+}
+
+pub async fn find_payments(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<DuePayment>>>, AppError> {
+    let payments = DueRepository::find_payments(&state.pool, uuid).await?;
+    Ok(Json(ApiResponse::new(payments)))
+}
+
+const DUE_EXPORT_HEADERS: &[&str] = &[
+    "Apellido",
+    "Nombre",
+    "CI",
+    "Mes",
+    "Año",
+    "Importe",
+    "Pagado",
+    "Saldo",
+    "Estado",
+    "Fecha de pago",
+];
+
+fn validate_filter(filter: &DueFilter) -> Result<(), AppError> {
+    let mut errors = vec![];
+    if matches!(filter.month, Some(month) if !(1..=12).contains(&month)) {
+        errors.push(FieldError::new("month", "El mes debe estar entre 1 y 12"));
+    }
+    AppError::validate(errors)
+}
+
+pub async fn find_all(
+    State(state): State<AppState>,
+    Query(filter): Query<DueFilter>,
+) -> Result<Json<ApiResponse<Vec<DueListItem>>>, AppError> {
+    validate_filter(&filter)?;
+    let dues = DueRepository::find_all(&state.pool, &filter).await?;
+    Ok(Json(ApiResponse::new(dues)))
+}
+
+pub async fn export(
+    State(state): State<AppState>,
+    Query(filter): Query<DueFilter>,
+    Query(export): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    validate_filter(&filter)?;
+    spreadsheet::export(
+        export.format,
+        "cuotas",
+        DUE_EXPORT_HEADERS,
+        move |mut writer| async move {
+            let mut dues = DueRepository::stream_all(&state.pool, &filter);
+            while let Some(due) = dues.try_next().await? {
+                writer
+                    .write(vec![
+                        due.lastname.into(),
+                        due.name.into(),
+                        due.ci.into(),
+                        due.month.into(),
+                        due.year.into(),
+                        due.amount.into(),
+                        due.amount_paid.into(),
+                        (due.amount - due.amount_paid).into(),
+                        if due.is_payed { "Pagada" } else { "Pendiente" }.into(),
+                        due.payment_date.into(),
+                    ])
+                    .await?;
+            }
+            Ok(writer)
+        },
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UnpaidQuery {
+    pub member_id: Option<Uuid>,
+}
+
+pub async fn find_unpaid(
+    State(state): State<AppState>,
+    Query(query): Query<UnpaidQuery>,
+) -> Result<Json<ApiResponse<Vec<UnpaidDue>>>, AppError> {
+    let dues = DueRepository::find_unpaid(&state.pool, query.member_id).await?;
+    Ok(Json(ApiResponse::new(dues)))
+}
+
+pub async fn find_member_unpaid(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<UnpaidDue>>>, AppError> {
+    let dues = DueRepository::find_unpaid(&state.pool, Some(uuid)).await?;
+    Ok(Json(ApiResponse::new(dues)))
+}
+
+pub async fn arrears(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Arrears>>>, AppError> {
+    let arrears = DueRepository::arrears(&state.pool).await?;
+    Ok(Json(ApiResponse::new(arrears)))
+}
@@ -0,0 +1,222 @@
+use axum::extract::State;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, FieldError},
+    helpers::{
+        dates,
+        extract::{Json, Path, Query},
+        validation,
+    },
+    models::{ci::Ci, employee::Employee},
+    repository::employee::{EmployeeData, EmployeeRepository},
+    utils::AppState,
+};
+
+use super::users::ApiResponse;
+
+#[derive(Debug, Deserialize)]
+pub struct EmployeeCreateRequest {
+    pub name: String,
+    pub lastname: String,
+    pub ci: Ci,
+    pub phone: String,
+    pub email: Option<String>,
+    pub address: String,
+    pub position: String,
+    pub hire_date: Option<NaiveDate>,
+    pub salary: Option<Decimal>,
+    pub medical_society_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmployeeUpdateRequest {
+    pub name: Option<String>,
+    pub lastname: Option<String>,
+    pub ci: Option<Ci>,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub address: Option<String>,
+    pub position: Option<String>,
+    pub hire_date: Option<NaiveDate>,
+    pub salary: Option<Decimal>,
+    pub medical_society_id: Option<Uuid>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmployeeListQuery {
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+pub async fn find_all(
+    State(state): State<AppState>,
+    Query(query): Query<EmployeeListQuery>,
+) -> Result<Json<ApiResponse<Vec<Employee>>>, AppError> {
+    let employees = EmployeeRepository::find_all(&state.pool, query.include_inactive).await?;
+    Ok(Json(ApiResponse::new(employees)))
+}
+
+pub async fn find_one(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Employee>>, AppError> {
+    let employee = EmployeeRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Funcionario no encontrado"))?;
+    Ok(Json(ApiResponse::new(employee)))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(body): Json<EmployeeCreateRequest>,
+) -> Result<Json<ApiResponse<Employee>>, AppError> {
+    let data = EmployeeData {
+        name: body.name.trim().to_string(),
+        lastname: body.lastname.trim().to_string(),
+        ci: body.ci,
+        phone: body.phone.trim().to_string(),
+        email: body
+            .email
+            .map(|email| email.trim().to_string())
+            .filter(|email| !email.is_empty()),
+        address: body.address.trim().to_string(),
+        position: body.position.trim().to_string(),
+        hire_date: body.hire_date.unwrap_or_else(dates::today),
+        salary: body.salary,
+        medical_society_id: body.medical_society_id,
+        is_active: true,
+    };
+    validate(&data)?;
+    ensure_unique_ci(&state.pool, &data.ci, None).await?;
+
+    let employee = EmployeeRepository::create(&state.pool, &data)
+        .await
+        .map_err(|e| {
+            AppError::from(e)
+                .on_invalid_reference("La mutualista indicada no existe")
+                .on_unique_violation(DUPLICATE_CI_MESSAGE)
+        })?;
+    Ok(Json(ApiResponse::new(employee)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<EmployeeUpdateRequest>,
+) -> Result<Json<ApiResponse<Employee>>, AppError> {
+    let employee = EmployeeRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Funcionario no encontrado"))?;
+
+    let mut data = EmployeeData::from(employee);
+    if let Some(name) = body.name {
+        data.name = name.trim().to_string();
+    }
+    if let Some(lastname) = body.lastname {
+        data.lastname = lastname.trim().to_string();
+    }
+    if let Some(ci) = body.ci {
+        data.ci = ci;
+    }
+    if let Some(phone) = body.phone {
+        data.phone = phone.trim().to_string();
+    }
+    if let Some(email) = body.email {
+        let email = email.trim();
+        data.email = (!email.is_empty()).then(|| email.to_string());
+    }
+    if let Some(address) = body.address {
+        data.address = address.trim().to_string();
+    }
+    if let Some(position) = body.position {
+        data.position = position.trim().to_string();
+    }
+    if let Some(hire_date) = body.hire_date {
+        data.hire_date = hire_date;
+    }
+    if let Some(salary) = body.salary {
+        data.salary = Some(salary);
+    }
+    if let Some(medical_society_id) = body.medical_society_id {
+        data.medical_society_id = medical_society_id;
+    }
+    if let Some(is_active) = body.is_active {
+        data.is_active = is_active;
+    }
+    validate(&data)?;
+    ensure_unique_ci(&state.pool, &data.ci, Some(uuid)).await?;
+
+    let employee = EmployeeRepository::update(&state.pool, uuid, &data)
+        .await
+        .map_err(|e| {
+            AppError::from(e)
+                .on_invalid_reference("La mutualista indicada no existe")
+                .on_unique_violation(DUPLICATE_CI_MESSAGE)
+        })?;
+    Ok(Json(ApiResponse::new(employee)))
+}
+
+pub async fn deactivate(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Employee>>, AppError> {
+    let employee = EmployeeRepository::deactivate(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Funcionario no encontrado"))?;
+    Ok(Json(ApiResponse::new(employee)))
+}
+
+const DUPLICATE_CI_MESSAGE: &str = "Ya existe un funcionario con esa cédula";
+
+async fn ensure_unique_ci(pool: &PgPool, ci: &Ci, exclude: Option<Uuid>) -> Result<(), AppError> {
+    match EmployeeRepository::find_id_by_ci(pool, ci).await? {
+        Some(existing_id) if Some(existing_id) != exclude => Err(AppError::Duplicate {
+            message: DUPLICATE_CI_MESSAGE.to_string(),
+            existing_id,
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn validate(data: &EmployeeData) -> Result<(), AppError> {
+    let mut errors = vec![];
+
+    if data.name.is_empty() {
+        errors.push(FieldError::new("name", "El nombre es obligatorio"));
+    }
+    if data.lastname.is_empty() {
+        errors.push(FieldError::new("lastname", "El apellido es obligatorio"));
+    }
+    if data.position.is_empty() {
+        errors.push(FieldError::new("position", "El cargo es obligatorio"));
+    }
+    if data.address.is_empty() {
+        errors.push(FieldError::new("address", "La dirección es obligatoria"));
+    }
+    if !validation::is_valid_phone(&data.phone) {
+        errors.push(FieldError::new(
+            "phone",
+            format!("El teléfono \"{}\" no es válido", data.phone),
+        ));
+    }
+    if matches!(&data.email, Some(email) if !email.contains('@')) {
+        errors.push(FieldError::new("email", "El email no es válido"));
+    }
+    if matches!(data.salary, Some(salary) if salary <= Decimal::ZERO) {
+        errors.push(FieldError::new("salary", "El sueldo debe ser mayor a cero"));
+    }
+    if data.hire_date > dates::today() {
+        errors.push(FieldError::new(
+            "hire_date",
+            "La fecha de ingreso no puede ser futura",
+        ));
+    }
+
+    AppError::validate(errors)
+}
@@ -1,24 +1,24 @@
-use axum::{
-    body::Body,
-    extract::State,
-    http::{Response, StatusCode},
-    response::IntoResponse,
-    Json,
-};
+use axum::extract::State;
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use sqlx::{prelude::FromRow, PgPool};
 use uuid::Uuid;
 
-use crate::utils::{AppState, Claims};
+use crate::{
+    errors::{AppError, FieldError},
+    helpers::{
+        extract::{Json, Path},
+        validation,
+    },
+    utils::AppState,
+};
+
+use super::users::ApiResponse;
 
 pub async fn get_medical_societies(
     State(state): State<AppState>,
-) -> Result<Json<MedicalSocietiesResponse>, MedicalSocietiesError> {
-    let medical_societies: Vec<MedicalSociety> = MedicalSociety::find_all(&state.pool)
-        .await
-        .unwrap_or(vec![]);
+) -> Result<Json<MedicalSocietiesResponse>, AppError> {
+    let medical_societies: Vec<MedicalSociety> = MedicalSociety::find_all(&state.pool).await?;
     let body = Json(MedicalSocietiesResponse {
         status: "success".to_string(),
         data: medical_societies,
@@ -26,26 +26,130 @@ pub async fn get_medical_societies(
     Ok(body)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum MedicalSocietiesError {
-    NotFound,
-    InternalServerError(String),
+pub async fn create(
+    State(state): State<AppState>,
+    Json(body): Json<MedicalSocietyRequest>,
+) -> Result<Json<ApiResponse<MedicalSociety>>, AppError> {
+    let name = body.name.unwrap_or_default().trim().to_string();
+    let emergency_phone = body.emergency_phone.unwrap_or_default().trim().to_string();
+    validate(&name, &emergency_phone)?;
+
+    if MedicalSociety::find_by_name(&state.pool, &name)
+        .await?
+        .is_some()
+    {
+        return Err(AppError::Conflict(format!(
+            "La mutualista \"{}\" ya existe",
+            name
+        )));
+    }
+
+    let medical_society = MedicalSociety::create(&state.pool, &name, &emergency_phone).await?;
+    Ok(Json(ApiResponse::new(medical_society)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<MedicalSocietyRequest>,
+) -> Result<Json<ApiResponse<MedicalSociety>>, AppError> {
+    let mut medical_society = MedicalSociety::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Mutualista no encontrada"))?;
+
+    if let Some(name) = body.name {
+        medical_society.name = name.trim().to_string();
+    }
+    if let Some(emergency_phone) = body.emergency_phone {
+        medical_society.emergency_phone = emergency_phone.trim().to_string();
+    }
+    validate(&medical_society.name, &medical_society.emergency_phone)?;
+
+    if let Some(existing) = MedicalSociety::find_by_name(&state.pool, &medical_society.name).await?
+    {
+        if existing.id != uuid {
+            return Err(AppError::Conflict(format!(
+                "La mutualista \"{}\" ya existe",
+                medical_society.name
+            )));
+        }
+    }
+
+    let medical_society = MedicalSociety::update(&state.pool, &medical_society).await?;
+    Ok(Json(ApiResponse::new(medical_society)))
+}
+
+// Solo se puede borrar una mutualista que no cubre a ningún socio ni funcionario
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Uuid>>, AppError> {
+    MedicalSociety::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Mutualista no encontrada"))?;
+
+    let (members, employees) = MedicalSociety::count_references(&state.pool, uuid).await?;
+    if members > 0 || employees > 0 {
+        return Err(AppError::Conflict(format!(
+            "La mutualista cubre a {} socios y {} funcionarios; cámbielos de mutualista antes de borrarla",
+            members, employees
+        )));
+    }
+
+    MedicalSociety::delete(&state.pool, uuid)
+        .await
+        .map_err(|e| {
+            AppError::from(e).on_in_use("La mutualista está asignada a socios o funcionarios")
+        })?;
+    Ok(Json(ApiResponse::new(uuid)))
 }
-impl IntoResponse for MedicalSocietiesError {
-    fn into_response(self) -> Response<Body> {
-        let (status, message) = match self {
-            MedicalSocietiesError::NotFound => (
-                StatusCode::NOT_FOUND,
-                "Medical Societies not found".to_string(),
+
+pub async fn coverage(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<MedicalSocietyCoverage>>>, AppError> {
+    let medical_societies = MedicalSociety::find_all(&state.pool).await?;
+    let people = MedicalSociety::find_covered_people(&state.pool).await?;
+
+    let coverage = medical_societies
+        .into_iter()
+        .map(|medical_society| {
+            let (members, employees): (Vec<_>, Vec<_>) = people
+                .iter()
+                .filter(|person| person.medical_society_id == medical_society.id)
+                .partition(|person| person.kind == "member");
+            MedicalSocietyCoverage {
+                id: medical_society.id,
+                name: medical_society.name,
+                emergency_phone: medical_society.emergency_phone,
+                members: members.into_iter().map(CoveredPerson::from).collect(),
+                employees: employees.into_iter().map(CoveredPerson::from).collect(),
+            }
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::new(coverage)))
+}
+
+fn validate(name: &str, emergency_phone: &str) -> Result<(), AppError> {
+    let mut errors = vec![];
+
+    if name.is_empty() {
+        errors.push(FieldError::new(
+            "name",
+            "El nombre de la mutualista es obligatorio",
+        ));
+    }
+    if !validation::is_valid_phone(emergency_phone) {
+        errors.push(FieldError::new(
+            "emergency_phone",
+            format!(
+                "El teléfono de emergencia \"{}\" no es válido",
+                emergency_phone
             ),
-            MedicalSocietiesError::InternalServerError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
-        };
-        let body = Json(json!({
-            "status": "error",
-            "error": message,
-        }));
-        (status, body).into_response()
+        ));
     }
+
+    AppError::validate(errors)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -54,6 +158,12 @@ pub struct MedicalSocietiesResponse {
     pub data: Vec<MedicalSociety>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MedicalSocietyRequest {
+    pub name: Option<String>,
+    pub emergency_phone: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct MedicalSociety {
     pub id: Uuid,
@@ -63,16 +173,172 @@ pub struct MedicalSociety {
     pub updated_at: NaiveDateTime,
 }
 
+#[derive(Debug, Serialize)]
+pub struct MedicalSocietyCoverage {
+    pub id: Uuid,
+    pub name: String,
+    pub emergency_phone: String,
+    pub members: Vec<CoveredPerson>,
+    pub employees: Vec<CoveredPerson>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoveredPerson {
+    pub id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub phone: String,
+}
+
+#[derive(Debug, FromRow)]
+struct CoveredPersonRow {
+    id: Uuid,
+    name: String,
+    lastname: String,
+    ci: String,
+    phone: String,
+    kind: String,
+    medical_society_id: Uuid,
+}
+
+impl From<&CoveredPersonRow> for CoveredPerson {
+    fn from(row: &CoveredPersonRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name.clone(),
+            lastname: row.lastname.clone(),
+            ci: row.ci.clone(),
+            phone: row.phone.clone(),
+        }
+    }
+}
+
 impl MedicalSociety {
     pub async fn find_all(pool: &PgPool) -> Result<Vec<MedicalSociety>, sqlx::Error> {
         let medical_societies = sqlx::query_as::<_, MedicalSociety>(
             r#"
             SELECT id, name, emergency_phone, created_at, updated_at
             FROM medical_society
+            ORDER BY name
             "#,
         )
         .fetch_all(pool)
         .await?;
         Ok(medical_societies)
     }
+
+    pub async fn find_by_id(
+        pool: &PgPool,
+        id: Uuid,
+    ) -> Result<Option<MedicalSociety>, sqlx::Error> {
+        sqlx::query_as::<_, MedicalSociety>(
+            r#"
+            SELECT id, name, emergency_phone, created_at, updated_at
+            FROM medical_society
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn find_by_name(
+        pool: &PgPool,
+        name: &str,
+    ) -> Result<Option<MedicalSociety>, sqlx::Error> {
+        sqlx::query_as::<_, MedicalSociety>(
+            r#"
+            SELECT id, name, emergency_phone, created_at, updated_at
+            FROM medical_society
+            WHERE lower(name) = lower($1)
+            "#,
+        )
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn create(
+        pool: &PgPool,
+        name: &str,
+        emergency_phone: &str,
+    ) -> Result<MedicalSociety, sqlx::Error> {
+        sqlx::query_as::<_, MedicalSociety>(
+            r#"
+            INSERT INTO medical_society (name, emergency_phone)
+            VALUES ($1, $2)
+            RETURNING id, name, emergency_phone, created_at, updated_at
+            "#,
+        )
+        .bind(name)
+        .bind(emergency_phone)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn update(
+        pool: &PgPool,
+        medical_society: &MedicalSociety,
+    ) -> Result<MedicalSociety, sqlx::Error> {
+        sqlx::query_as::<_, MedicalSociety>(
+            r#"
+            UPDATE medical_society
+            SET name = $1, emergency_phone = $2, updated_at = current_timestamp
+            WHERE id = $3
+            RETURNING id, name, emergency_phone, created_at, updated_at
+            "#,
+        )
+        .bind(&medical_society.name)
+        .bind(&medical_society.emergency_phone)
+        .bind(medical_society.id)
+        .fetch_one(pool)
+        .await
+    }
+
+    // Socios y funcionarios que tienen asignada la mutualista
+    pub async fn count_references(pool: &PgPool, id: Uuid) -> Result<(i64, i64), sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM members WHERE medical_society_id = $1),
+                (SELECT COUNT(*) FROM employees WHERE medical_society_id = $1)
+            "#,
+        )
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM medical_society
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    // Socios y funcionarios activos, con la mutualista que los cubre
+    async fn find_covered_people(pool: &PgPool) -> Result<Vec<CoveredPersonRow>, sqlx::Error> {
+        sqlx::query_as::<_, CoveredPersonRow>(
+            r#"
+            SELECT id, name, lastname, ci, phone, 'member' AS kind, medical_society_id
+            FROM members
+            WHERE is_active
+            UNION ALL
+            SELECT id, name, lastname, ci, phone, 'employee' AS kind, medical_society_id
+            FROM employees
+            WHERE is_active
+            ORDER BY lastname, name
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
 }
@@ -0,0 +1,460 @@
+use std::collections::HashMap;
+
+use axum::extract::{Multipart, State};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, FieldError},
+    helpers::{
+        extract::{Json, Query},
+        spreadsheet,
+    },
+    models::ci::Ci,
+    utils::AppState,
+};
+
+use super::{medical_society::MedicalSociety, members::Member, users::ApiResponse};
+
+// Por defecto solo se valida; hay que pedir ?mode=commit para guardar
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    #[default]
+    DryRun,
+    Commit,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub mode: ImportMode,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub total_rows: usize,
+    pub valid_rows: usize,
+    pub imported: usize,
+    pub errors: Vec<RowError>,
+    pub duplicates: Vec<DuplicateRow>,
+}
+
+// `row` es el número de fila en la planilla, tal como se ve al abrirla
+#[derive(Debug, Serialize)]
+pub struct RowError {
+    pub row: usize,
+    pub errors: Vec<FieldError>,
+}
+
+// Cédula que ya tiene un socio (`existing_id`) o que se repite en otra fila del archivo
+#[derive(Debug, Serialize)]
+pub struct DuplicateRow {
+    pub row: usize,
+    pub ci: Ci,
+    pub existing_id: Option<Uuid>,
+    pub duplicate_of_row: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Column {
+    Name,
+    Lastname,
+    Ci,
+    BirthDate,
+    Phone,
+    TutorName,
+    TutorLastname,
+    TutorPhone,
+    Observation,
+    MedicalSociety,
+    Address,
+    IsActive,
+}
+
+// Encabezados aceptados para cada columna, ya normalizados (minúsculas, sin tildes, con _)
+const COLUMN_HEADERS: [(Column, &[&str]); 12] = [
+    (Column::Name, &["nombre", "name"]),
+    (Column::Lastname, &["apellido", "lastname"]),
+    (Column::Ci, &["ci", "cedula"]),
+    (
+        Column::BirthDate,
+        &[
+            "fecha_de_nacimiento",
+            "fecha_nacimiento",
+            "nacimiento",
+            "birth_date",
+        ],
+    ),
+    (Column::Phone, &["telefono", "celular", "phone"]),
+    (
+        Column::TutorName,
+        &["nombre_del_tutor", "nombre_tutor", "tutor_name"],
+    ),
+    (
+        Column::TutorLastname,
+        &["apellido_del_tutor", "apellido_tutor", "tutor_lastname"],
+    ),
+    (
+        Column::TutorPhone,
+        &["telefono_del_tutor", "telefono_tutor", "tutor_phone"],
+    ),
+    (
+        Column::Observation,
+        &["observaciones", "observacion", "observation"],
+    ),
+    (
+        Column::MedicalSociety,
+        &["mutualista", "sociedad_medica", "medical_society"],
+    ),
+    (Column::Address, &["direccion", "domicilio", "address"]),
+    (Column::IsActive, &["activo", "is_active"]),
+];
+
+const REQUIRED_COLUMNS: [(Column, &str); 7] = [
+    (Column::Name, "nombre"),
+    (Column::Lastname, "apellido"),
+    (Column::Ci, "ci"),
+    (Column::BirthDate, "fecha_de_nacimiento"),
+    (Column::Phone, "telefono"),
+    (Column::MedicalSociety, "mutualista"),
+    (Column::Address, "direccion"),
+];
+
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%d/%m/%Y", "%d-%m-%Y"];
+
+pub async fn import(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse<ImportReport>>, AppError> {
+    let mut file = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(e.body_text()))?
+    {
+        if field.name() == Some("file") {
+            let file_name = field.file_name().unwrap_or_default().to_string();
+            let bytes = field
+                .bytes()
+                .await
+                .map_err(|e| AppError::BadRequest(e.body_text()))?;
+            file = Some((file_name, bytes));
+        }
+    }
+    let (file_name, bytes) = file.ok_or(AppError::Validation(vec![FieldError::new(
+        "file",
+        "Adjunte el archivo en el campo \"file\"",
+    )]))?;
+
+    let mut rows = spreadsheet::read_rows(&file_name, &bytes)?.into_iter();
+    let header = rows.next().map(|row| row.cells).unwrap_or_default();
+    let columns = map_columns(&header)?;
+
+    let medical_societies: HashMap<String, Uuid> = MedicalSociety::find_all(&state.pool)
+        .await?
+        .into_iter()
+        .map(|medical_society| (normalize(&medical_society.name), medical_society.id))
+        .collect();
+
+    let mut report = ImportReport {
+        mode: query.mode,
+        total_rows: 0,
+        valid_rows: 0,
+        imported: 0,
+        errors: vec![],
+        duplicates: vec![],
+    };
+    let mut members: Vec<(usize, Member)> = vec![];
+    for sheet_row in rows {
+        if sheet_row.cells.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let row = sheet_row.line;
+        report.total_rows += 1;
+        match parse_member(&sheet_row.cells, &columns, &medical_societies) {
+            Ok(member) => members.push((row, member)),
+            Err(errors) => report.errors.push(RowError { row, errors }),
+        }
+    }
+
+    // Duplicados dentro del propio archivo y contra los socios ya registrados
+    let mut seen: HashMap<Ci, usize> = HashMap::new();
+    let cis: Vec<Ci> = members
+        .iter()
+        .map(|(_, member)| member.ci.clone())
+        .collect();
+    let existing: HashMap<Ci, Uuid> = Member::find_ids_by_ci(&state.pool, &cis)
+        .await?
+        .into_iter()
+        .map(|(id, ci)| (ci, id))
+        .collect();
+    for (row, member) in &members {
+        let existing_id = existing.get(&member.ci).copied();
+        let duplicate_of_row = seen.get(&member.ci).copied();
+        if existing_id.is_some() || duplicate_of_row.is_some() {
+            report.duplicates.push(DuplicateRow {
+                row: *row,
+                ci: member.ci.clone(),
+                existing_id,
+                duplicate_of_row,
+            });
+        }
+        seen.entry(member.ci.clone()).or_insert(*row);
+    }
+    report.valid_rows = members.len() - report.duplicates.len();
+
+    if query.mode == ImportMode::DryRun {
+        return Ok(Json(ApiResponse::new(report)));
+    }
+    if !report.errors.is_empty() || !report.duplicates.is_empty() {
+        let report =
+            serde_json::to_value(&report).map_err(|e| AppError::Internal(e.to_string()))?;
+        return Err(AppError::ImportRejected(report));
+    }
+
+    // Todo o nada: si falla un socio se descarta la transacción completa
+    let mut tx = state.pool.begin().await?;
+    for (_, member) in members {
+        Member::create(&mut tx, member).await.map_err(|e| {
+            AppError::from(e).on_unique_violation("Ya existe un socio con esa cédula")
+        })?;
+        report.imported += 1;
+    }
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::new(report)))
+}
+
+fn map_columns(header: &[String]) -> Result<HashMap<Column, usize>, AppError> {
+    let mut columns = HashMap::new();
+    for (index, title) in header.iter().enumerate() {
+        let key = normalize(title).replace(' ', "_");
+        let column = COLUMN_HEADERS
+            .iter()
+            .find(|(_, aliases)| aliases.contains(&key.as_str()))
+            .map(|(column, _)| *column);
+        if let Some(column) = column {
+            columns.entry(column).or_insert(index);
+        }
+    }
+
+    let errors = REQUIRED_COLUMNS
+        .iter()
+        .filter(|(column, _)| !columns.contains_key(column))
+        .map(|(_, title)| FieldError::new("file", format!("Falta la columna \"{}\"", title)))
+        .collect();
+    AppError::validate(errors)?;
+    Ok(columns)
+}
+
+fn parse_member(
+    cells: &[String],
+    columns: &HashMap<Column, usize>,
+    medical_societies: &HashMap<String, Uuid>,
+) -> Result<Member, Vec<FieldError>> {
+    let cell = |column: Column| {
+        columns
+            .get(&column)
+            .and_then(|index| cells.get(*index))
+            .map(|value| value.trim())
+            .unwrap_or_default()
+    };
+    let optional =
+        |column: Column| Some(cell(column).to_string()).filter(|value| !value.is_empty());
+    let mut errors = vec![];
+
+    let birth_date = DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(cell(Column::BirthDate), format).ok());
+    if birth_date.is_none() {
+        errors.push(FieldError::new(
+            "birth_date",
+            format!(
+                "La fecha de nacimiento \"{}\" no es válida",
+                cell(Column::BirthDate)
+            ),
+        ));
+    }
+
+    let medical_society_id = medical_societies
+        .get(&normalize(cell(Column::MedicalSociety)))
+        .copied();
+    if medical_society_id.is_none() {
+        errors.push(FieldError::new(
+            "medical_society",
+            format!(
+                "La mutualista \"{}\" no existe",
+                cell(Column::MedicalSociety)
+            ),
+        ));
+    }
+
+    let ci = Ci::parse(cell(Column::Ci))
+        .map_err(|e| errors.push(FieldError::new("ci", e.to_string())))
+        .ok();
+
+    let is_active = match normalize(cell(Column::IsActive)).as_str() {
+        "" | "si" | "s" | "true" | "1" | "x" => true,
+        "no" | "n" | "false" | "0" => false,
+        other => {
+            errors.push(FieldError::new(
+                "is_active",
+                format!("El valor \"{}\" no es válido para activo (si/no)", other),
+            ));
+            true
+        }
+    };
+
+    // Sin una cédula válida no se puede armar el socio; se informa lo encontrado hasta acá
+    let Some(ci) = ci else {
+        return Err(errors);
+    };
+    let now = chrono::Local::now().naive_local();
+    let member = Member {
+        id: Uuid::nil(),
+        name: cell(Column::Name).to_string(),
+        lastname: cell(Column::Lastname).to_string(),
+        ci,
+        birth_date: birth_date.unwrap_or_default(),
+        phone: cell(Column::Phone).to_string(),
+        tutor_name: optional(Column::TutorName),
+        tutor_lastname: optional(Column::TutorLastname),
+        tutor_phone: optional(Column::TutorPhone),
+        observation: optional(Column::Observation),
+        medical_society_id: medical_society_id.unwrap_or_default(),
+        address: cell(Column::Address).to_string(),
+        is_active,
+        created_at: now,
+        updated_at: now,
+    };
+    if let Err(AppError::Validation(field_errors)) = member.validate() {
+        errors.extend(field_errors);
+    }
+
+    if errors.is_empty() {
+        Ok(member)
+    } else {
+        Err(errors)
+    }
+}
+
+// Minúsculas, sin tildes y con los espacios colapsados, para comparar encabezados y nombres
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' => 'a',
+            'é' => 'e',
+            'í' => 'i',
+            'ó' | 'ö' => 'o',
+            'ú' | 'ü' => 'u',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn header() -> Vec<String> {
+        strings(&[
+            "Nombre",
+            "Apellido",
+            "Cédula",
+            "Fecha de nacimiento",
+            "Teléfono",
+            "Mutualista",
+            "Dirección",
+            "Activo",
+        ])
+    }
+
+    fn societies() -> (Uuid, HashMap<String, Uuid>) {
+        let id = Uuid::new_v4();
+        (id, HashMap::from([(normalize("Médica Uruguaya"), id)]))
+    }
+
+    #[test]
+    fn normalize_removes_accents_case_and_extra_spaces() {
+        assert_eq!(normalize("  Médica   URUGUAYA "), "medica uruguaya");
+        assert_eq!(normalize("Pingüino Ñandú"), "pinguino ñandu");
+    }
+
+    #[test]
+    fn map_columns_accepts_aliases_in_any_order() {
+        let columns = map_columns(&header()).unwrap();
+        assert_eq!(columns[&Column::Ci], 2);
+        assert_eq!(columns[&Column::BirthDate], 3);
+        assert_eq!(columns[&Column::IsActive], 7);
+        assert!(!columns.contains_key(&Column::TutorName));
+    }
+
+    #[test]
+    fn map_columns_reports_missing_required_columns() {
+        let Err(AppError::Validation(errors)) = map_columns(&strings(&["nombre", "apellido"]))
+        else {
+            panic!("se esperaba un error de validación");
+        };
+        let messages: Vec<_> = errors.iter().map(|error| error.message.as_str()).collect();
+        assert!(messages.contains(&"Falta la columna \"ci\""));
+        assert!(messages.contains(&"Falta la columna \"direccion\""));
+        assert_eq!(errors.len(), 5);
+    }
+
+    #[test]
+    fn parse_member_reads_a_valid_row() {
+        let columns = map_columns(&header()).unwrap();
+        let (society_id, societies) = societies();
+        let cells = strings(&[
+            "Ana",
+            "Pérez",
+            "1.234.567-2",
+            "15/03/1990",
+            "099 123 456",
+            "medica uruguaya",
+            "Av. Italia 1234",
+            "no",
+        ]);
+
+        let member = parse_member(&cells, &columns, &societies).unwrap();
+        assert_eq!(member.ci.as_str(), "12345672");
+        assert_eq!(
+            member.birth_date,
+            NaiveDate::from_ymd_opt(1990, 3, 15).unwrap()
+        );
+        assert_eq!(member.medical_society_id, society_id);
+        assert!(!member.is_active);
+    }
+
+    #[test]
+    fn parse_member_collects_every_error_of_the_row() {
+        let columns = map_columns(&header()).unwrap();
+        let (_, societies) = societies();
+        let cells = strings(&[
+            "Ana",
+            "Pérez",
+            "12345678",
+            "31/02/1990",
+            "099 123 456",
+            "Otra",
+            "Av. Italia 1234",
+            "tal vez",
+        ]);
+
+        let errors = parse_member(&cells, &columns, &societies).unwrap_err();
+        let fields: Vec<_> = errors.iter().map(|error| error.field).collect();
+        assert_eq!(fields, ["birth_date", "medical_society", "ci", "is_active"]);
+    }
+}
@@ -1,74 +1,289 @@
-use axum::{
-    body::Body,
-    extract::{Path, Query, State},
-    http::{Response, StatusCode},
-    response::IntoResponse,
-    Json,
-};
-use chrono::{NaiveDate, NaiveDateTime};
+use axum::{extract::State, response::Response};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use futures_util::{stream::BoxStream, TryStreamExt};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use sqlx::{prelude::FromRow, PgPool, postgres::PgHasArrayType};
+use sqlx::{
+    postgres::PgArguments, prelude::FromRow, query::QueryAs, PgConnection, PgPool, Postgres,
+};
 use uuid::Uuid;
 
-use crate::utils::AppState;
+use crate::{
+    errors::{AppError, FieldError},
+    helpers::{
+        dates,
+        extract::{Json, Path, Query},
+        pagination::{PageQuery, Pagination},
+        spreadsheet::{self, ExportQuery},
+        validation,
+    },
+    middlewares::auth::AuthUser,
+    models::{activity::Activity, ci::Ci, due::Due, schedule::Weekday},
+    utils::AppState,
+};
 
 use super::users::ApiResponse;
 
-pub async fn find_all(State(state): State<AppState>) -> Result<Json<FindAllResponse>, UserError> {
-    let members = Member::find_all(&state.pool)
-        .await
-        .map_err(|e| UserError::InternalServerError(e.to_string()))?;
+pub async fn find_all(
+    State(state): State<AppState>,
+    Query(filter): Query<MemberFilter>,
+    Query(sort): Query<MemberSort>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<FindAllResponse>, AppError> {
+    filter.validate()?;
+
+    let total = Member::count_filtered(&state.pool, &filter).await?;
+    let members = Member::find_page(&state.pool, &filter, &sort, &page).await?;
     Ok(Json(FindAllResponse {
         status: "success".to_string(),
         data: members,
+        pagination: Pagination::new(&page, total),
     }))
 }
 
-/*pub async fn find_one(
+const MEMBER_EXPORT_HEADERS: &[&str] = &[
+    "Apellido",
+    "Nombre",
+    "CI",
+    "Fecha de nacimiento",
+    "Teléfono",
+    "Dirección",
+    "Mutualista",
+    "Actividades",
+    "Nombre del tutor",
+    "Apellido del tutor",
+    "Teléfono del tutor",
+    "Observaciones",
+    "Activo",
+];
+
+// Exporta los socios con los mismos filtros y orden que el listado, pero sin paginar
+pub async fn export(
+    State(state): State<AppState>,
+    Query(filter): Query<MemberFilter>,
+    Query(sort): Query<MemberSort>,
+    Query(export): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    filter.validate()?;
+    let query = Member::export_sql(&sort);
+    spreadsheet::export(
+        export.format,
+        "socios",
+        MEMBER_EXPORT_HEADERS,
+        move |mut writer| async move {
+            let mut members = Member::stream_export(&state.pool, &query, &filter);
+            while let Some(member) = members.try_next().await? {
+                writer
+                    .write(vec![
+                        member.lastname.into(),
+                        member.name.into(),
+                        member.ci.into(),
+                        member.birth_date.into(),
+                        member.phone.into(),
+                        member.address.into(),
+                        member.medical_society_name.into(),
+                        member.activities.into(),
+                        member.tutor_name.into(),
+                        member.tutor_lastname.into(),
+                        member.tutor_phone.into(),
+                        member.observation.into(),
+                        member.is_active.into(),
+                    ])
+                    .await?;
+            }
+            Ok(writer)
+        },
+    )
+}
+
+// Filtros del listado de socios; los comparte cualquier consulta que liste socios
+#[derive(Debug, Default, Deserialize)]
+pub struct MemberFilter {
+    pub medical_society_id: Option<Uuid>,
+    pub activity_id: Option<Uuid>,
+    pub min_age: Option<i32>,
+    pub max_age: Option<i32>,
+    #[serde(default)]
+    pub minors_only: bool,
+    pub has_unpaid_dues: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+impl MemberFilter {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut errors = vec![];
+
+        if matches!(self.min_age, Some(age) if age < 0) {
+            errors.push(FieldError::new(
+                "min_age",
+                "La edad mínima no puede ser negativa",
+            ));
+        }
+        if let (Some(min_age), Some(max_age)) = (self.min_age, self.max_age) {
+            if min_age > max_age {
+                errors.push(FieldError::new(
+                    "max_age",
+                    "La edad máxima debe ser mayor o igual a la mínima",
+                ));
+            }
+        }
+
+        AppError::validate(errors)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemberSortField {
+    #[default]
+    Lastname,
+    CreatedAt,
+    BirthDate,
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MemberSort {
+    pub sort: Option<MemberSortField>,
+    pub order: Option<SortOrder>,
+}
+
+impl MemberSort {
+    // Cláusula ORDER BY armada solo con valores fijos; el id desempata para que las páginas sean estables
+    fn order_by(&self) -> String {
+        let direction = match self.order.unwrap_or_default() {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        match self.sort.unwrap_or_default() {
+            MemberSortField::Lastname => {
+                format!("m.lastname {0}, m.name {0}, m.id", direction)
+            }
+            MemberSortField::CreatedAt => format!("m.created_at {}, m.id", direction),
+            MemberSortField::BirthDate => format!("m.birth_date {}, m.id", direction),
+        }
+    }
+}
+
+pub async fn find_one(
     State(state): State<AppState>,
     Path(uuid): Path<Uuid>,
-) -> Result<Json<FindOneResponse>, UserError> {
-    let member = Member::find_by_id(&state.pool, uuid).await;
+) -> Result<Json<FindOneResponse>, AppError> {
+    let member = Member::find_full_detail(&state.pool, uuid).await?;
     match member {
-        None => return Err(UserError::NotFound),
+        None => Err(AppError::NotFound("Socio no encontrado")),
         Some(member) => Ok(Json(FindOneResponse {
             status: "success".to_string(),
             data: member,
         })),
     }
 }
-*/
+
 #[derive(Deserialize)]
-pub struct QuerySearch {
-    pub name: Option<String>,
-    pub lastname: Option<String>,
+pub struct EmergencyCardQuery {
+    pub member_id: Option<Uuid>,
+    pub ci: Option<String>,
+}
+
+// Datos esenciales para atender un incidente; cada consulta queda registrada
+pub async fn emergency_card(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<EmergencyCardQuery>,
+) -> Result<Json<ApiResponse<EmergencyCard>>, AppError> {
+    let row = match (query.member_id, query.ci) {
+        (Some(member_id), _) => Member::find_emergency_card_by_id(&state.pool, member_id).await,
+        (None, Some(ci)) => Member::find_emergency_card_by_ci(&state.pool, &ci).await,
+        (None, None) => {
+            return Err(AppError::BadRequest(
+                "Indique el id o la cédula del socio".to_string(),
+            ))
+        }
+    }?
+    .ok_or(AppError::NotFound("Socio no encontrado"))?;
+
+    Member::log_emergency_card_access(&state.pool, row.id, &auth_user.user.email).await?;
+
+    Ok(Json(ApiResponse::new(EmergencyCard::from(row))))
+}
+
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<i64>,
 }
-pub async fn get_match_by_name(
+
+// Busca por nombre, apellido, cédula o teléfono; los resultados más parecidos primero
+pub async fn search(
     State(state): State<AppState>,
-    Query(query): Query<QuerySearch>,
-) -> Result<Json<ApiResponse<Vec<MemberSmall>>>, UserError> {
-    let member = Member::find_match_by_name(&state.pool, query.name, query.lastname).await?;
-    Ok(Json(ApiResponse::new(member)))
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<ApiResponse<Vec<MemberSearchResult>>>, AppError> {
+    // Los comodines de LIKE se descartan para que la búsqueda sea siempre literal
+    let text: String = query
+        .q
+        .trim()
+        .chars()
+        .filter(|c| *c != '%' && *c != '_')
+        .collect();
+    if text.is_empty() {
+        return Err(AppError::Validation(vec![FieldError::new(
+            "q",
+            "Ingrese un texto para buscar",
+        )]));
+    }
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let members = Member::search(&state.pool, &text, limit).await?;
+    Ok(Json(ApiResponse::new(members)))
 }
 
 pub async fn create(
     State(state): State<AppState>,
     Json(member): Json<Member>,
-) -> Result<Json<CreateResponse>, UserError> {
-    let member = Member::create(&state.pool, member)
-        .await
-        .map_err(|e| UserError::InternalServerError(e.to_string()))?;
+) -> Result<Json<CreateResponse>, AppError> {
+    member.validate()?;
+    ensure_unique_ci(&state.pool, &member.ci, None).await?;
+    let mut conn = state.pool.acquire().await?;
+    let member = Member::create(&mut conn, member).await.map_err(|e| {
+        AppError::from(e)
+            .on_invalid_reference("La mutualista indicada no existe")
+            .on_unique_violation(DUPLICATE_CI_MESSAGE)
+    })?;
     Ok(Json(CreateResponse {
         status: "success".to_string(),
         data: member,
     }))
 }
+const DUPLICATE_CI_MESSAGE: &str = "Ya existe un socio con esa cédula";
+
+// Rechaza la cédula si ya la tiene otro socio, indicando cuál para que el cliente pueda abrirlo
+async fn ensure_unique_ci(pool: &PgPool, ci: &Ci, exclude: Option<Uuid>) -> Result<(), AppError> {
+    match Member::find_id_by_ci(pool, ci).await? {
+        Some(existing_id) if Some(existing_id) != exclude => Err(AppError::Duplicate {
+            message: DUPLICATE_CI_MESSAGE.to_string(),
+            existing_id,
+        }),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct UpdateMemberBody {
     name: Option<String>,
     lastname: Option<String>,
-    ci: Option<String>,
+    ci: Option<Ci>,
     birth_date: Option<NaiveDate>,
     phone: Option<String>,
     tutor_name: Option<String>,
@@ -77,9 +292,12 @@ pub struct UpdateMemberBody {
     observation: Option<String>,
     medical_society_id: Option<Uuid>,
     address: Option<String>,
+    is_active: Option<bool>,
+    // Valor de updated_at que el cliente leyó; si cambió, otro usuario editó el socio
+    updated_at: NaiveDateTime,
 }
 impl Member {
-    pub fn update_my_member(&mut self, new_data: UpdateMemberBody) -> () {
+    pub fn update_my_member(&mut self, new_data: UpdateMemberBody) {
         if let Some(name) = new_data.name {
             self.name = name;
         }
@@ -113,69 +331,113 @@ impl Member {
         if let Some(address) = new_data.address {
             self.address = address;
         }
+        if let Some(is_active) = new_data.is_active {
+            self.is_active = is_active;
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        let mut errors = vec![];
+
+        if self.name.trim().is_empty() {
+            errors.push(FieldError::new("name", "El nombre es obligatorio"));
+        }
+        if self.lastname.trim().is_empty() {
+            errors.push(FieldError::new("lastname", "El apellido es obligatorio"));
+        }
+        if !validation::is_valid_phone(&self.phone) {
+            errors.push(FieldError::new(
+                "phone",
+                format!("El teléfono \"{}\" no es válido", self.phone),
+            ));
+        }
+
+        let today = dates::today();
+        if self.birth_date > today {
+            errors.push(FieldError::new(
+                "birth_date",
+                "La fecha de nacimiento no puede ser futura",
+            ));
+        } else if dates::age_at(self.birth_date, today) < 18 {
+            let is_blank = |field: &Option<String>| {
+                field
+                    .as_deref()
+                    .map(str::trim)
+                    .unwrap_or_default()
+                    .is_empty()
+            };
+            if is_blank(&self.tutor_name) || is_blank(&self.tutor_lastname) {
+                errors.push(FieldError::new(
+                    "tutor_name",
+                    "El nombre y apellido del tutor son obligatorios para menores",
+                ));
+            }
+            match &self.tutor_phone {
+                Some(phone) if validation::is_valid_phone(phone) => {}
+                _ => errors.push(FieldError::new(
+                    "tutor_phone",
+                    "El teléfono del tutor es obligatorio para menores",
+                )),
+            }
+        }
+
+        AppError::validate(errors)
     }
 }
-/*pub async fn update(
+
+pub async fn update(
     State(state): State<AppState>,
     Path(uuid): Path<Uuid>,
     Json(update_member_body): Json<UpdateMemberBody>,
-) -> Result<Json<UpdateResponse>, UserError> {
-    let mut member = Member::find_by_id(&state.pool, uuid.clone())
-        .await
-        .ok_or(UserError::NotFound)?;
+) -> Result<Json<UpdateResponse>, AppError> {
+    let mut member = Member::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Socio no encontrado"))?;
 
+    let expected_updated_at = update_member_body.updated_at;
     member.update_my_member(update_member_body);
+    member.validate()?;
+    ensure_unique_ci(&state.pool, &member.ci, Some(uuid)).await?;
 
-    let updated_member = Member::update(&state.pool, member)
+    let updated_member = Member::update(&state.pool, member, expected_updated_at)
         .await
-        .map_err(|e| UserError::InternalServerError(e.to_string()))?;
+        .map_err(|e| {
+            AppError::from(e)
+                .on_invalid_reference("La mutualista indicada no existe")
+                .on_unique_violation(DUPLICATE_CI_MESSAGE)
+        })?
+        .ok_or(AppError::Conflict(
+            "El socio fue modificado por otro usuario, recargue los datos".to_string(),
+        ))?;
     Ok(Json(UpdateResponse {
         status: "success".to_string(),
         data: updated_member,
     }))
 }
-*/
+
 pub async fn delete(
     State(state): State<AppState>,
     Path(uuid): Path<Uuid>,
-) -> Result<Json<DeleteResponse>, UserError> {
-    Member::delete(&state.pool, uuid)
-        .await
-        .map_err(|e| UserError::InternalServerError(e.to_string()))?;
+) -> Result<Json<DeleteResponse>, AppError> {
+    Member::delete(&state.pool, uuid).await.map_err(|e| {
+        AppError::from(e)
+            .on_in_use("El socio tiene cuotas, actividades u otros registros asociados")
+    })?;
     Ok(Json(DeleteResponse {
         status: "success".to_string(),
     }))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum UserError {
-    NotFound,
-    InternalServerError(String),
-}
-
-impl IntoResponse for UserError {
-    fn into_response(self) -> Response<Body> {
-        let (status, message) = match self {
-            UserError::NotFound => (StatusCode::NOT_FOUND, "User not found".to_string()),
-            UserError::InternalServerError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
-        };
-
-        let body = Json(json!({
-            "status": "error",
-            "error": message,
-        }));
-        (status, body).into_response()
-    }
-}
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FindAllResponse {
     pub status: String,
     pub data: Vec<Member>,
+    pub pagination: Pagination,
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FindOneResponse {
     pub status: String,
-    pub data: Member,
+    pub data: FullMemberDetail,
 }
 #[derive(Debug, Serialize, Deserialize)]
 
@@ -194,187 +456,552 @@ pub struct DeleteResponse {
     pub status: String,
 }
 
+// Socio con el nombre de la mutualista y sus actividades, para la exportación
+#[derive(Debug, FromRow)]
+pub struct MemberExportRow {
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub birth_date: NaiveDate,
+    pub phone: String,
+    pub address: String,
+    pub medical_society_name: String,
+    pub activities: Option<String>,
+    pub tutor_name: Option<String>,
+    pub tutor_lastname: Option<String>,
+    pub tutor_phone: Option<String>,
+    pub observation: Option<String>,
+    pub is_active: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Member {
+    pub id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: Ci,
+    pub birth_date: NaiveDate,
+    pub phone: String,
+    pub tutor_name: Option<String>,
+    pub tutor_lastname: Option<String>,
+    pub tutor_phone: Option<String>,
+    pub observation: Option<String>,
+    pub medical_society_id: Uuid,
+    pub address: String,
+    #[serde(default = "default_is_active")]
+    pub is_active: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+fn default_is_active() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FullMemberDetail {
+    pub id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: Ci,
+    pub birth_date: NaiveDate,
+    pub phone: String,
+    pub tutor_name: Option<String>,
+    pub tutor_lastname: Option<String>,
+    pub tutor_phone: Option<String>,
+    pub observation: Option<String>,
+    pub medical_society: MedicalSocietyInfo,
+    pub activities: Vec<MemberActivity>,
+    pub address: String,
+    pub is_active: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub dues: Vec<Due>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct MedicalSocietyInfo {
+    pub id: Uuid,
+    pub name: String,
+    pub emergency_phone: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MemberActivity {
+    pub id: Uuid,
+    pub name: String,
+    pub category: Option<String>,
+    pub schedule: Vec<MemberActivitySlot>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct MemberActivitySlot {
+    pub id: Uuid,
+    #[serde(skip_serializing)]
+    pub activity_id: Uuid,
+    pub day: Weekday,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub space_id: Uuid,
+    pub space_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmergencyCard {
+    pub id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub age: i32,
+    pub is_minor: bool,
+    pub phone: String,
+    pub observation: Option<String>,
+    pub tutor: Option<TutorContact>,
+    pub medical_society_name: String,
+    pub emergency_phone: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TutorContact {
+    pub name: Option<String>,
+    pub lastname: Option<String>,
+    pub phone: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct EmergencyCardRow {
     id: Uuid,
     name: String,
     lastname: String,
     ci: String,
     birth_date: NaiveDate,
     phone: String,
+    observation: Option<String>,
     tutor_name: Option<String>,
     tutor_lastname: Option<String>,
     tutor_phone: Option<String>,
-    observation: Option<String>,
-    medical_society_id: Uuid,
-    address: String,
-    created_at: NaiveDateTime,
-    updated_at: NaiveDateTime,
+    medical_society_name: String,
+    emergency_phone: String,
 }
 
+impl From<EmergencyCardRow> for EmergencyCard {
+    fn from(row: EmergencyCardRow) -> Self {
+        let age = dates::age_at(row.birth_date, dates::today());
+        let is_minor = age < 18;
+        Self {
+            id: row.id,
+            name: row.name,
+            lastname: row.lastname,
+            ci: row.ci,
+            age,
+            is_minor,
+            phone: row.phone,
+            observation: row.observation,
+            tutor: is_minor.then_some(TutorContact {
+                name: row.tutor_name,
+                lastname: row.tutor_lastname,
+                phone: row.tutor_phone,
+            }),
+            medical_society_name: row.medical_society_name,
+            emergency_phone: row.emergency_phone,
+        }
+    }
+}
 
 #[derive(Serialize, Deserialize, FromRow)]
-pub struct MemberSmall {
+pub struct MemberSearchResult {
     pub id: Uuid,
     pub name: String,
     pub lastname: String,
-    pub ci: String,
+    pub ci: Ci,
+    pub phone: String,
     pub birth_date: NaiveDate,
+    pub score: f32,
 }
 
-impl Member {
+// Condiciones de MemberFilter sobre el alias m de members; ocupan los parámetros $1 a $7
+const MEMBER_FILTER_SQL: &str = r#"
+    ($1::uuid IS NULL OR m.medical_society_id = $1)
+    AND ($2::uuid IS NULL OR EXISTS (
+        SELECT 1 FROM members_activities ma
+        WHERE ma.member_id = m.id AND ma.activity_id = $2
+    ))
+    AND ($3::int IS NULL OR extract(year FROM age(current_date, m.birth_date))::int >= $3)
+    AND ($4::int IS NULL OR extract(year FROM age(current_date, m.birth_date))::int <= $4)
+    AND (NOT $5 OR age(current_date, m.birth_date) < interval '18 years')
+    AND ($6::boolean IS NULL OR EXISTS (
+        SELECT 1 FROM dues d WHERE d.member_id = m.id AND NOT d.is_payed
+    ) = $6)
+    AND ($7::boolean IS NULL OR m.is_active = $7)
+"#;
+
+fn bind_member_filter<'q, O>(
+    query: QueryAs<'q, Postgres, O, PgArguments>,
+    filter: &MemberFilter,
+) -> QueryAs<'q, Postgres, O, PgArguments> {
+    query
+        .bind(filter.medical_society_id)
+        .bind(filter.activity_id)
+        .bind(filter.min_age)
+        .bind(filter.max_age)
+        .bind(filter.minors_only)
+        .bind(filter.has_unpaid_dues)
+        .bind(filter.is_active)
+}
 
-    pub async fn find_match_by_name(
+impl Member {
+    // La cédula y el teléfono se comparan solo por sus dígitos, y a partir de 3 dígitos
+    pub async fn search(
         pool: &PgPool,
-        name: Option<String>,
-        lastname: Option<String>,
-    ) -> Result<Vec<MemberSmall>, UserError> {
-        if name.is_some() && lastname.is_some() {
-            let query: Vec<MemberSmall> = sqlx::query_as(
+        text: &str,
+        limit: i64,
+    ) -> Result<Vec<MemberSearchResult>, sqlx::Error> {
+        let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+        let digits = if digits.len() >= 3 {
+            digits
+        } else {
+            String::new()
+        };
+
+        sqlx::query_as::<_, MemberSearchResult>(
             r#"
-            SELECT id, name, lastname, ci, birth_date
+            SELECT id, name, lastname, ci, phone, birth_date,
+                   greatest(
+                       word_similarity(lower(immutable_unaccent($1)), member_search_name(name, lastname)),
+                       CASE
+                           WHEN member_search_name(name, lastname) LIKE lower(immutable_unaccent($1)) || '%' THEN 0.9
+                           WHEN member_search_name(name, lastname) LIKE '%' || lower(immutable_unaccent($1)) || '%' THEN 0.8
+                           ELSE 0
+                       END,
+                       CASE
+                           WHEN $2 = '' THEN 0
+                           WHEN ci = $2 THEN 1
+                           WHEN ci LIKE '%' || $2 || '%'
+                             OR regexp_replace(phone, '\D', '', 'g') LIKE '%' || $2 || '%' THEN 0.85
+                           ELSE 0
+                       END
+                   )::real AS score
             FROM members
-            WHERE name ILIKE $1 AND lastname ILIKE $2 
+            WHERE lower(immutable_unaccent($1)) <% member_search_name(name, lastname)
+               OR member_search_name(name, lastname) LIKE '%' || lower(immutable_unaccent($1)) || '%'
+               OR ($2 <> '' AND (
+                   ci LIKE '%' || $2 || '%'
+                   OR regexp_replace(phone, '\D', '', 'g') LIKE '%' || $2 || '%'
+               ))
+            ORDER BY score DESC, lastname, name
+            LIMIT $3
             "#,
         )
-        .bind(format!("{}%", name.unwrap()))
-        .bind(format!("{}%", lastname.unwrap()))
+        .bind(text)
+        .bind(digits)
+        .bind(limit)
         .fetch_all(pool)
         .await
-        .map_err(|e| UserError::InternalServerError(e.to_string()))?;
-            return Ok(query);
-        } else if name.is_some() && lastname.is_none() {
-            let query = sqlx::query_as(
-                r#"
-                SELECT id, name, lastname, ci, birth_date
-                FROM members
-                WHERE name ILIKE $1
-                "#,
-            )
-            .bind(format!("{}%", name.unwrap()))
-            .fetch_all(pool)
-            .await
-            .map_err(|e| UserError::InternalServerError(e.to_string()))?;
-            return Ok(query);
-        } else if name.is_none() && lastname.is_some() {
-            let query = sqlx::query_as(
-                r#"
-                SELECT id, name, lastname, ci, birth_date
-                FROM members
-                WHERE lastname ILIKE $1
-                "#,
-            )
-            .bind(format!("{}%", lastname.unwrap()))
+    }
+
+    pub async fn count_filtered(pool: &PgPool, filter: &MemberFilter) -> Result<i64, sqlx::Error> {
+        let query = format!("SELECT COUNT(*) FROM members m WHERE {}", MEMBER_FILTER_SQL);
+        let (total,) = bind_member_filter(sqlx::query_as::<_, (i64,)>(&query), filter)
+            .fetch_one(pool)
+            .await?;
+        Ok(total)
+    }
+
+    // La consulta se arma afuera de stream_export porque el stream la toma prestada
+    pub fn export_sql(sort: &MemberSort) -> String {
+        format!(
+            r#"
+            SELECT m.name, m.lastname, m.ci, m.birth_date, m.phone, m.address,
+                   ms.name AS medical_society_name,
+                   (
+                       SELECT string_agg(a.name, ', ' ORDER BY a.name)
+                       FROM members_activities ma
+                       INNER JOIN activities a ON a.id = ma.activity_id
+                       WHERE ma.member_id = m.id
+                   ) AS activities,
+                   m.tutor_name, m.tutor_lastname, m.tutor_phone, m.observation, m.is_active
+            FROM members m
+            INNER JOIN medical_society ms ON ms.id = m.medical_society_id
+            WHERE {}
+            ORDER BY {}
+            "#,
+            MEMBER_FILTER_SQL,
+            sort.order_by()
+        )
+    }
+
+    pub fn stream_export<'a>(
+        pool: &'a PgPool,
+        query: &'a str,
+        filter: &MemberFilter,
+    ) -> BoxStream<'a, Result<MemberExportRow, sqlx::Error>> {
+        bind_member_filter(sqlx::query_as::<_, MemberExportRow>(query), filter).fetch(pool)
+    }
+
+    pub async fn find_page(
+        pool: &PgPool,
+        filter: &MemberFilter,
+        sort: &MemberSort,
+        page: &PageQuery,
+    ) -> Result<Vec<Member>, sqlx::Error> {
+        let query = format!(
+            r#"
+            SELECT m.id, m.name, m.lastname, m.ci, m.birth_date, m.phone, m.tutor_name, m.tutor_lastname, m.tutor_phone, m.observation, m.medical_society_id, m.address, m.is_active, m.created_at, m.updated_at
+            FROM members m
+            WHERE {}
+            ORDER BY {}
+            LIMIT $8 OFFSET $9
+            "#,
+            MEMBER_FILTER_SQL,
+            sort.order_by()
+        );
+        bind_member_filter(sqlx::query_as::<_, Member>(&query), filter)
+            .bind(page.per_page())
+            .bind(page.offset())
             .fetch_all(pool)
             .await
-            .map_err(|e| UserError::InternalServerError(e.to_string()))?;
-            return Ok(query);
-        } else {
-            return Err(UserError::InternalServerError(
-                "No se ingresaron datos".to_string(),
-            ));
-        }
     }
 
-    pub async fn find_all(pool: &PgPool) -> Result<Vec<Member>, sqlx::Error> {
-        let members = sqlx::query_as::<_, Member>(
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Member>, sqlx::Error> {
+        let member = sqlx::query_as::<_, Member>(
             r#"
-            SELECT id, name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, created_at, updated_at
+            SELECT id, name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, is_active, created_at, updated_at
             FROM members
+            WHERE id = $1
             "#,
         )
-        .fetch_all(pool)
+        .bind(id)
+        .fetch_optional(pool)
         .await?;
-        Ok(members)
+        Ok(member)
     }
 
-    /*pub struct FullMemberDetail {
+    pub async fn find_emergency_card_by_id(
+        pool: &PgPool,
         id: Uuid,
-        name: String,
-        lastname: String,
-        ci: String,
-        birth_date: NaiveDate,
-        phone: String,
-        tutor_name: Option<String>,
-        tutor_lastname: Option<String>,
-        tutor_phone: Option<String>,
-        observation: Option<String>,
-        medical_society: MedicalSocietyInfo,
-        activities: Vec<Activity>,
-        address: String,
-        created_at: NaiveDateTime,
-        updated_at: NaiveDateTime,
-        dues: Vec<Due>,
+    ) -> Result<Option<EmergencyCardRow>, sqlx::Error> {
+        sqlx::query_as::<_, EmergencyCardRow>(
+            r#"
+            SELECT m.id, m.name, m.lastname, m.ci, m.birth_date, m.phone, m.observation, m.tutor_name, m.tutor_lastname, m.tutor_phone,
+                   ms.name AS medical_society_name, ms.emergency_phone
+            FROM members m
+            INNER JOIN medical_society ms ON ms.id = m.medical_society_id
+            WHERE m.id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
     }
 
-    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Option<Member> {
-        let query = sqlx::query_as(
+    // La cédula se guarda solo con dígitos, así "1.234.567-2" encuentra a "12345672"
+    pub async fn find_emergency_card_by_ci(
+        pool: &PgPool,
+        ci: &str,
+    ) -> Result<Option<EmergencyCardRow>, sqlx::Error> {
+        let digits: String = ci.chars().filter(|c| c.is_ascii_digit()).collect();
+        sqlx::query_as::<_, EmergencyCardRow>(
             r#"
-            SELECT id, name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, created_at, updated_at
-            FROM members
-            WHERE id = $1
+            SELECT m.id, m.name, m.lastname, m.ci, m.birth_date, m.phone, m.observation, m.tutor_name, m.tutor_lastname, m.tutor_phone,
+                   ms.name AS medical_society_name, ms.emergency_phone
+            FROM members m
+            INNER JOIN medical_society ms ON ms.id = m.medical_society_id
+            WHERE m.ci = $1
             "#,
         )
-        .bind(id)
+        .bind(digits)
         .fetch_optional(pool)
         .await
-        .unwrap();
-        match query {
-            Some(member) => Some(member),
-            None => None,
-        }
-    }*/
+    }
+
+    pub async fn find_id_by_ci(pool: &PgPool, ci: &Ci) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar("SELECT id FROM members WHERE ci = $1")
+            .bind(ci)
+            .fetch_optional(pool)
+            .await
+    }
+
+    // Devuelve (id, ci) de los socios que ya tienen alguna de las cédulas
+    pub async fn find_ids_by_ci(pool: &PgPool, cis: &[Ci]) -> Result<Vec<(Uuid, Ci)>, sqlx::Error> {
+        sqlx::query_as("SELECT id, ci FROM members WHERE ci = ANY($1)")
+            .bind(cis)
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn log_emergency_card_access(
+        pool: &PgPool,
+        member_id: Uuid,
+        accessed_by: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO emergency_card_accesses (member_id, accessed_by)
+            VALUES ($1, $2)
+            "#,
+        )
+        .bind(member_id)
+        .bind(accessed_by)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    // Arma la ficha completa del socio: mutualista, actividades con sus horarios y cuotas
+    pub async fn find_full_detail(
+        pool: &PgPool,
+        id: Uuid,
+    ) -> Result<Option<FullMemberDetail>, sqlx::Error> {
+        let member = match Member::find_by_id(pool, id).await? {
+            Some(member) => member,
+            None => return Ok(None),
+        };
+
+        let medical_society = sqlx::query_as::<_, MedicalSocietyInfo>(
+            r#"
+            SELECT id, name, emergency_phone
+            FROM medical_society
+            WHERE id = $1
+            "#,
+        )
+        .bind(member.medical_society_id)
+        .fetch_one(pool)
+        .await?;
+
+        let activities = sqlx::query_as::<_, Activity>(
+            r#"
+            SELECT a.id, a.name, a.category, a.retired_at, a.created_at, a.updated_at
+            FROM activities a
+            INNER JOIN members_activities ma ON ma.activity_id = a.id
+            WHERE ma.member_id = $1
+            ORDER BY a.name
+            "#,
+        )
+        .bind(member.id)
+        .fetch_all(pool)
+        .await?;
+
+        let activity_ids: Vec<Uuid> = activities.iter().map(|activity| activity.id).collect();
+        let mut schedule = sqlx::query_as::<_, MemberActivitySlot>(
+            r#"
+            SELECT s.id, s.activity_id, s.day, s.start_time, s.end_time, s.space_id, sp.name AS space_name
+            FROM activities_schedule s
+            INNER JOIN space sp ON sp.id = s.space_id
+            WHERE s.activity_id = ANY($1)
+            ORDER BY s.day, s.start_time
+            "#,
+        )
+        .bind(&activity_ids)
+        .fetch_all(pool)
+        .await?;
+
+        let activities = activities
+            .into_iter()
+            .map(|activity| {
+                let (slots, rest) = schedule
+                    .drain(..)
+                    .partition(|slot| slot.activity_id == activity.id);
+                schedule = rest;
+                MemberActivity {
+                    id: activity.id,
+                    name: activity.name,
+                    category: activity.category,
+                    schedule: slots,
+                }
+            })
+            .collect();
+
+        let dues = sqlx::query_as::<_, Due>(
+            r#"
+            SELECT id, member_id, amount, amount_paid, payment_date, month, year, is_payed, created_at, updated_at
+            FROM dues
+            WHERE member_id = $1
+            ORDER BY year DESC, month DESC
+            "#,
+        )
+        .bind(member.id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Some(FullMemberDetail {
+            id: member.id,
+            name: member.name,
+            lastname: member.lastname,
+            ci: member.ci,
+            birth_date: member.birth_date,
+            phone: member.phone,
+            tutor_name: member.tutor_name,
+            tutor_lastname: member.tutor_lastname,
+            tutor_phone: member.tutor_phone,
+            observation: member.observation,
+            medical_society,
+            activities,
+            address: member.address,
+            is_active: member.is_active,
+            created_at: member.created_at,
+            updated_at: member.updated_at,
+            dues,
+        }))
+    }
 
-    pub async fn create(pool: &PgPool, member: Member) -> Result<Member, sqlx::Error> {
+    pub async fn create(conn: &mut PgConnection, member: Member) -> Result<Member, sqlx::Error> {
         let member = sqlx::query_as::<_, Member>(
             r#"
-            INSERT INTO members (name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-            RETURNING id, name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, created_at, updated_at
+            INSERT INTO members (name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, is_active, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            RETURNING id, name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, is_active, created_at, updated_at
             "#,
         )
         .bind(&member.name)
         .bind(&member.lastname)
         .bind(&member.ci)
-        .bind(&member.birth_date)
+        .bind(member.birth_date)
         .bind(&member.phone)
         .bind(&member.tutor_name)
         .bind(&member.tutor_lastname)
         .bind(&member.tutor_phone)
         .bind(&member.observation)
-        .bind(&member.medical_society_id)
+        .bind(member.medical_society_id)
         .bind(&member.address)
-        .bind(&member.created_at)
-        .bind(&member.updated_at)
-        .fetch_one(pool)
+        .bind(member.is_active)
+        .bind(member.created_at)
+        .bind(member.updated_at)
+        .fetch_one(conn)
         .await?;
 
         Ok(member)
     }
 
-    pub async fn update(pool: &PgPool, member: Member) -> Result<Member, sqlx::Error> {
+    // Devuelve None si el socio fue modificado después de `expected_updated_at`
+    pub async fn update(
+        pool: &PgPool,
+        member: Member,
+        expected_updated_at: NaiveDateTime,
+    ) -> Result<Option<Member>, sqlx::Error> {
         let member = sqlx::query_as::<_, Member>(
             r#"
             UPDATE members
-            SET name = $1, lastname = $2, ci = $3, birth_date = $4, phone = $5, tutor_name = $6, tutor_lastname = $7, tutor_phone = $8, observation = $9, medical_society_id = $10, address = $11, updated_at = $12
-            WHERE id = $13
-            RETURNING id, name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, created_at, updated_at
+            SET name = $1, lastname = $2, ci = $3, birth_date = $4, phone = $5, tutor_name = $6, tutor_lastname = $7, tutor_phone = $8, observation = $9, medical_society_id = $10, address = $11, is_active = $12, updated_at = $13
+            WHERE id = $14 AND updated_at = $15
+            RETURNING id, name, lastname, ci, birth_date, phone, tutor_name, tutor_lastname, tutor_phone, observation, medical_society_id, address, is_active, created_at, updated_at
             "#,
         )
         .bind(&member.name)
         .bind(&member.lastname)
         .bind(&member.ci)
-        .bind(&member.birth_date)
+        .bind(member.birth_date)
         .bind(&member.phone)
         .bind(&member.tutor_name)
         .bind(&member.tutor_lastname)
         .bind(&member.tutor_phone)
         .bind(&member.observation)
-        .bind(&member.medical_society_id)
+        .bind(member.medical_society_id)
         .bind(&member.address)
-        .bind(chrono::DateTime::from_timestamp(chrono::Local::now().timestamp(), 0))
-        .bind(&member.id)
-        .fetch_one(pool)
+        .bind(member.is_active)
+        .bind(chrono::Local::now().naive_local())
+        .bind(member.id)
+        .bind(expected_updated_at)
+        .fetch_optional(pool)
         .await?;
 
         Ok(member)
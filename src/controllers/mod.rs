@@ -1,6 +1,15 @@
+pub mod activities;
+pub mod dues;
+pub mod employees;
 pub mod medical_society;
+pub mod member_import;
 pub mod members;
+pub mod payroll;
+pub mod rents;
+pub mod schedule;
 pub mod spaces;
+pub mod two_factor;
+pub mod user_management;
 pub mod users;
 //pub mod members;
 
@@ -0,0 +1,123 @@
+use axum::extract::State;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    helpers::{
+        dates,
+        extract::{Json, Path, Query},
+    },
+    models::employee::{EmployeePayment, PayrollLine, PayrollTotal},
+    repository::{employee::EmployeeRepository, payroll::PayrollRepository},
+    utils::AppState,
+};
+
+use super::users::ApiResponse;
+
+#[derive(Debug, Deserialize)]
+pub struct PeriodRequest {
+    pub month: i32,
+    pub year: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PayrollRunResponse {
+    pub month: i32,
+    pub year: i32,
+    pub created: usize,
+    pub payments: Vec<EmployeePayment>,
+    // Funcionarios activos que quedaron fuera por no tener sueldo cargado
+    pub without_salary: Vec<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PayrollPaymentRequest {
+    pub payment_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PayrollSummary {
+    pub month: i32,
+    pub year: i32,
+    #[serde(flatten)]
+    pub total: PayrollTotal,
+    pub lines: Vec<PayrollLine>,
+}
+
+pub async fn payroll_run(
+    State(state): State<AppState>,
+    Json(body): Json<PeriodRequest>,
+) -> Result<Json<ApiResponse<PayrollRunResponse>>, AppError> {
+    validate_period(body.month, body.year)?;
+
+    let payments = PayrollRepository::payroll_run(&state.pool, body.month, body.year).await?;
+    let without_salary = PayrollRepository::find_without_salary(&state.pool).await?;
+    Ok(Json(ApiResponse::new(PayrollRunResponse {
+        month: body.month,
+        year: body.year,
+        created: payments.len(),
+        payments,
+        without_salary,
+    })))
+}
+
+pub async fn register_payment(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<PayrollPaymentRequest>,
+) -> Result<Json<ApiResponse<EmployeePayment>>, AppError> {
+    let payment_date = body.payment_date.unwrap_or_else(dates::today);
+    if payment_date > dates::today() {
+        return Err(AppError::BadRequest(
+            "La fecha de pago no puede ser futura".to_string(),
+        ));
+    }
+
+    let payment = PayrollRepository::mark_payed(&state.pool, uuid, payment_date)
+        .await?
+        .ok_or(AppError::BadRequest(
+            "El pago no existe o ya fue registrado".to_string(),
+        ))?;
+    Ok(Json(ApiResponse::new(payment)))
+}
+
+pub async fn find_by_employee(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<EmployeePayment>>>, AppError> {
+    EmployeeRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Funcionario no encontrado"))?;
+    let payments = PayrollRepository::find_by_employee(&state.pool, uuid).await?;
+    Ok(Json(ApiResponse::new(payments)))
+}
+
+pub async fn summary(
+    State(state): State<AppState>,
+    Query(query): Query<PeriodRequest>,
+) -> Result<Json<ApiResponse<PayrollSummary>>, AppError> {
+    validate_period(query.month, query.year)?;
+
+    let total = PayrollRepository::total_by_period(&state.pool, query.month, query.year).await?;
+    let lines = PayrollRepository::find_by_period(&state.pool, query.month, query.year).await?;
+    Ok(Json(ApiResponse::new(PayrollSummary {
+        month: query.month,
+        year: query.year,
+        total,
+        lines,
+    })))
+}
+
+fn validate_period(month: i32, year: i32) -> Result<(), AppError> {
+    if !(1..=12).contains(&month) {
+        return Err(AppError::BadRequest(
+            "El mes debe estar entre 1 y 12".to_string(),
+        ));
+    }
+    if !(2000..=2100).contains(&year) {
+        return Err(AppError::BadRequest(format!("Año inválido: {}", year)));
+    }
+    Ok(())
+}
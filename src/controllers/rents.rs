@@ -0,0 +1,375 @@
+use axum::{extract::State, response::Response};
+use chrono::{NaiveDate, NaiveTime};
+use futures_util::TryStreamExt;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    helpers::{
+        dates,
+        extract::{Json, Path, Query},
+        spreadsheet::{self, ExportQuery},
+    },
+    models::{rent::Rent, schedule::Weekday},
+    repository::{
+        rent::{NewRent, RentRepository},
+        schedule::ScheduleRepository,
+        space::CreateSpaceRepository,
+    },
+    utils::AppState,
+};
+
+use super::users::ApiResponse;
+
+#[derive(Debug, Deserialize)]
+pub struct QuoteRequest {
+    pub space_id: Uuid,
+    pub rent_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuoteResponse {
+    pub space_id: Uuid,
+    pub space_name: String,
+    pub rent_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub hours: Decimal,
+    pub cost: Option<Decimal>,
+    pub available: bool,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RentCreateRequest {
+    pub full_name: String,
+    pub phone: String,
+    pub space_id: Uuid,
+    pub rent_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    // Si no se indica se cotiza con la tarifa del espacio
+    pub cost: Option<Decimal>,
+    #[serde(default)]
+    pub is_payed: bool,
+    pub payment_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RentListQuery {
+    pub space_id: Option<Uuid>,
+    pub date: Option<NaiveDate>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub include_cancelled: bool,
+}
+
+impl RentListQuery {
+    // ?date= tiene prioridad sobre el rango from/to
+    fn date_range(&self) -> (Option<NaiveDate>, Option<NaiveDate>) {
+        match self.date {
+            Some(date) => (Some(date), Some(date)),
+            None => (self.from, self.to),
+        }
+    }
+}
+
+const RENT_EXPORT_HEADERS: &[&str] = &[
+    "Fecha",
+    "Desde",
+    "Hasta",
+    "Espacio",
+    "Cliente",
+    "Teléfono",
+    "Costo",
+    "Pagado",
+    "Fecha de pago",
+    "Cancelado",
+];
+
+#[derive(Debug, Deserialize)]
+pub struct RentPaymentRequest {
+    pub payment_date: Option<NaiveDate>,
+}
+
+pub async fn quote(
+    State(state): State<AppState>,
+    Json(body): Json<QuoteRequest>,
+) -> Result<Json<ApiResponse<QuoteResponse>>, AppError> {
+    validate_window(body.rent_date, body.start_time, body.end_time)?;
+    let (space_name, hourly_price) = RentRepository::find_space_rate(&state.pool, body.space_id)
+        .await?
+        .ok_or(AppError::BadRequest(
+            "El espacio indicado no existe".to_string(),
+        ))?;
+
+    let hours = hours_between(body.start_time, body.end_time);
+    let mut conn = state.pool.acquire().await?;
+    let conflicts = find_conflicts(
+        &mut conn,
+        body.space_id,
+        body.rent_date,
+        body.start_time,
+        body.end_time,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::new(QuoteResponse {
+        space_id: body.space_id,
+        space_name,
+        rent_date: body.rent_date,
+        start_time: body.start_time,
+        end_time: body.end_time,
+        hours,
+        cost: hourly_price.map(|price| (price * hours).round_dp(2)),
+        available: conflicts.is_empty(),
+        conflicts,
+    })))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(body): Json<RentCreateRequest>,
+) -> Result<Json<ApiResponse<Rent>>, AppError> {
+    if body.full_name.trim().is_empty() || body.phone.trim().is_empty() {
+        return Err(AppError::BadRequest(
+            "El nombre y el teléfono de quien alquila son obligatorios".to_string(),
+        ));
+    }
+    validate_window(body.rent_date, body.start_time, body.end_time)?;
+
+    let (space_name, hourly_price) = RentRepository::find_space_rate(&state.pool, body.space_id)
+        .await?
+        .ok_or(AppError::BadRequest(
+            "El espacio indicado no existe".to_string(),
+        ))?;
+    let cost = match (body.cost, hourly_price) {
+        (Some(cost), _) if cost < Decimal::ZERO => {
+            return Err(AppError::BadRequest(
+                "El costo no puede ser negativo".to_string(),
+            ))
+        }
+        (Some(cost), _) => cost,
+        (None, Some(price)) => (price * hours_between(body.start_time, body.end_time)).round_dp(2),
+        (None, None) => {
+            return Err(AppError::BadRequest(format!(
+                "\"{}\" no tiene tarifa por hora, indique el costo",
+                space_name
+            )))
+        }
+    };
+
+    // Con el espacio bloqueado, la verificación de conflictos y el alta no se intercalan con
+    // otro alquiler u horario del mismo espacio
+    let mut tx = state.pool.begin().await?;
+    if CreateSpaceRepository::find_by_id_for_update(&mut tx, body.space_id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::BadRequest(
+            "El espacio indicado no existe".to_string(),
+        ));
+    }
+    let conflicts = find_conflicts(
+        &mut tx,
+        body.space_id,
+        body.rent_date,
+        body.start_time,
+        body.end_time,
+    )
+    .await?;
+    if !conflicts.is_empty() {
+        return Err(AppError::Conflict(conflicts.join(". ")));
+    }
+
+    let payment_date = if body.is_payed {
+        Some(body.payment_date.unwrap_or_else(dates::today))
+    } else {
+        None
+    };
+    let rent = RentRepository::create(
+        &mut tx,
+        NewRent {
+            full_name: body.full_name.trim().to_string(),
+            phone: body.phone.trim().to_string(),
+            space_id: body.space_id,
+            rent_date: body.rent_date,
+            start_time: body.start_time,
+            end_time: body.end_time,
+            cost,
+            is_payed: body.is_payed,
+            payment_date,
+        },
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(Json(ApiResponse::new(rent)))
+}
+
+pub async fn find_all(
+    State(state): State<AppState>,
+    Query(query): Query<RentListQuery>,
+) -> Result<Json<ApiResponse<Vec<Rent>>>, AppError> {
+    let (from, to) = query.date_range();
+    let rents = RentRepository::find_all(
+        &state.pool,
+        query.space_id,
+        from,
+        to,
+        query.include_cancelled,
+    )
+    .await?;
+    Ok(Json(ApiResponse::new(rents)))
+}
+
+pub async fn export(
+    State(state): State<AppState>,
+    Query(query): Query<RentListQuery>,
+    Query(export): Query<ExportQuery>,
+) -> Result<Response, AppError> {
+    let (from, to) = query.date_range();
+    spreadsheet::export(
+        export.format,
+        "alquileres",
+        RENT_EXPORT_HEADERS,
+        move |mut writer| async move {
+            let sql = RentRepository::export_sql();
+            let mut rents = RentRepository::stream_export(
+                &state.pool,
+                &sql,
+                query.space_id,
+                from,
+                to,
+                query.include_cancelled,
+            );
+            while let Some(rent) = rents.try_next().await? {
+                writer
+                    .write(vec![
+                        rent.rent_date.into(),
+                        rent.start_time.into(),
+                        rent.end_time.into(),
+                        rent.space_name.into(),
+                        rent.full_name.into(),
+                        rent.phone.into(),
+                        rent.cost.into(),
+                        rent.is_payed.into(),
+                        rent.payment_date.into(),
+                        rent.cancelled_at.is_some().into(),
+                    ])
+                    .await?;
+            }
+            Ok(writer)
+        },
+    )
+}
+
+pub async fn find_one(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Rent>>, AppError> {
+    let rent = RentRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Alquiler no encontrado"))?;
+    Ok(Json(ApiResponse::new(rent)))
+}
+
+pub async fn cancel(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Rent>>, AppError> {
+    let rent = RentRepository::cancel(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Alquiler no encontrado"))?;
+    Ok(Json(ApiResponse::new(rent)))
+}
+
+pub async fn register_payment(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<RentPaymentRequest>,
+) -> Result<Json<ApiResponse<Rent>>, AppError> {
+    let payment_date = body.payment_date.unwrap_or_else(dates::today);
+    if payment_date > dates::today() {
+        return Err(AppError::BadRequest(
+            "La fecha de pago no puede ser futura".to_string(),
+        ));
+    }
+    let rent = RentRepository::mark_payed(&state.pool, uuid, payment_date)
+        .await?
+        .ok_or(AppError::BadRequest(
+            "El alquiler no existe, está cancelado o ya fue pagado".to_string(),
+        ))?;
+    Ok(Json(ApiResponse::new(rent)))
+}
+
+fn validate_window(
+    rent_date: NaiveDate,
+    start_time: NaiveTime,
+    end_time: NaiveTime,
+) -> Result<(), AppError> {
+    if end_time <= start_time {
+        return Err(AppError::BadRequest(
+            "La hora de fin debe ser posterior a la de inicio".to_string(),
+        ));
+    }
+    if rent_date < dates::today() {
+        return Err(AppError::BadRequest(
+            "No se puede alquilar en una fecha pasada".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn hours_between(start_time: NaiveTime, end_time: NaiveTime) -> Decimal {
+    Decimal::from((end_time - start_time).num_minutes()) / Decimal::from(60)
+}
+
+// Actividades del día de la semana y otros alquileres de la fecha que ocupan el espacio
+async fn find_conflicts(
+    conn: &mut PgConnection,
+    space_id: Uuid,
+    rent_date: NaiveDate,
+    start_time: NaiveTime,
+    end_time: NaiveTime,
+) -> Result<Vec<String>, AppError> {
+    let slots = ScheduleRepository::find_overlapping_slots(
+        &mut *conn,
+        space_id,
+        Weekday::of(rent_date),
+        start_time,
+        end_time,
+        None,
+    )
+    .await?;
+    let rents =
+        RentRepository::find_overlapping(conn, space_id, rent_date, start_time, end_time, None)
+            .await?;
+
+    let mut conflicts: Vec<String> = slots
+        .iter()
+        .map(|slot| {
+            format!(
+                "{} ocupa \"{}\" de {} a {}",
+                slot.activity_name,
+                slot.space_name,
+                slot.start_time.format("%H:%M"),
+                slot.end_time.format("%H:%M")
+            )
+        })
+        .collect();
+    conflicts.extend(rents.iter().map(|rent| {
+        // La cotización es pública: no se expone quién alquila
+        format!(
+            "Ya está alquilado de {} a {}",
+            rent.start_time.format("%H:%M"),
+            rent.end_time.format("%H:%M")
+        )
+    }));
+    Ok(conflicts)
+}
@@ -0,0 +1,304 @@
+use axum::extract::State;
+use chrono::{Datelike, Days, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, FieldError},
+    helpers::{
+        dates,
+        extract::{Json, Path, Query},
+    },
+    models::schedule::{ScheduleSlot, ScheduleSlotDetail, Weekday},
+    repository::{
+        activity::ActivityRepository, rent::RentRepository, schedule::ScheduleRepository,
+        space::CreateSpaceRepository,
+    },
+    utils::AppState,
+};
+
+use super::users::ApiResponse;
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleCreateRequest {
+    pub activity_id: Uuid,
+    pub day: Weekday,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub space_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleUpdateRequest {
+    pub day: Option<Weekday>,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>,
+    pub space_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeekQuery {
+    // Cualquier fecha de la semana a mostrar; por defecto la semana actual
+    pub week: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeekSchedule {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: Vec<DaySchedule>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DaySchedule {
+    pub day: Weekday,
+    pub date: NaiveDate,
+    pub spaces: Vec<SpaceSchedule>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpaceSchedule {
+    pub space_id: Uuid,
+    pub space_name: String,
+    pub slots: Vec<WeekSlot>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WeekSlot {
+    pub id: Uuid,
+    pub activity_id: Uuid,
+    pub activity_name: String,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+pub async fn week(
+    State(state): State<AppState>,
+    Query(query): Query<WeekQuery>,
+) -> Result<Json<ApiResponse<WeekSchedule>>, AppError> {
+    let date = query.week.unwrap_or_else(dates::today);
+    // En los extremos del calendario la semana puede no existir completa
+    let out_of_range = || {
+        AppError::Validation(vec![FieldError::new(
+            "week",
+            "La fecha está fuera del rango admitido",
+        )])
+    };
+    let monday = date
+        .checked_sub_days(Days::new(date.weekday().num_days_from_monday() as u64))
+        .ok_or_else(out_of_range)?;
+    let sunday = monday
+        .checked_add_days(Days::new(6))
+        .ok_or_else(out_of_range)?;
+
+    let slots = ScheduleRepository::find_all_detailed(&state.pool).await?;
+    let days = Weekday::ALL
+        .iter()
+        .zip(monday.iter_days())
+        .map(|(day, date)| DaySchedule {
+            day: *day,
+            date,
+            spaces: group_by_space(slots.iter().filter(|slot| slot.day == *day)),
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::new(WeekSchedule {
+        from: monday,
+        to: sunday,
+        days,
+    })))
+}
+
+// Los horarios llegan ordenados por espacio y hora, así que alcanza con agrupar consecutivos
+fn group_by_space<'a>(slots: impl Iterator<Item = &'a ScheduleSlotDetail>) -> Vec<SpaceSchedule> {
+    let mut spaces: Vec<SpaceSchedule> = vec![];
+    for slot in slots {
+        let entry = WeekSlot {
+            id: slot.id,
+            activity_id: slot.activity_id,
+            activity_name: slot.activity_name.clone(),
+            start_time: slot.start_time,
+            end_time: slot.end_time,
+        };
+        match spaces.last_mut() {
+            Some(space) if space.space_id == slot.space_id => space.slots.push(entry),
+            _ => spaces.push(SpaceSchedule {
+                space_id: slot.space_id,
+                space_name: slot.space_name.clone(),
+                slots: vec![entry],
+            }),
+        }
+    }
+    spaces
+}
+
+pub async fn find_one(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<ScheduleSlot>>, AppError> {
+    let slot = ScheduleRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Horario no encontrado"))?;
+    Ok(Json(ApiResponse::new(slot)))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(body): Json<ScheduleCreateRequest>,
+) -> Result<Json<ApiResponse<ScheduleSlot>>, AppError> {
+    let activity = ActivityRepository::find_by_id(&state.pool, body.activity_id)
+        .await?
+        .ok_or(AppError::BadRequest(
+            "La actividad indicada no existe".to_string(),
+        ))?;
+    if activity.retired_at.is_some() {
+        return Err(AppError::BadRequest(format!(
+            "La actividad \"{}\" fue dada de baja",
+            activity.name
+        )));
+    }
+
+    // Con el espacio bloqueado, la verificación y el alta no se intercalan con otro horario o
+    // alquiler del mismo espacio
+    let mut tx = state.pool.begin().await?;
+    check_availability(
+        &mut tx,
+        body.space_id,
+        body.day,
+        body.start_time,
+        body.end_time,
+        None,
+    )
+    .await?;
+
+    let slot = ScheduleRepository::create(
+        &mut tx,
+        body.activity_id,
+        body.day,
+        body.start_time,
+        body.end_time,
+        body.space_id,
+    )
+    .await
+    .map_err(map_space_error)?;
+    tx.commit().await?;
+    Ok(Json(ApiResponse::new(slot)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<ScheduleUpdateRequest>,
+) -> Result<Json<ApiResponse<ScheduleSlot>>, AppError> {
+    let mut slot = ScheduleRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Horario no encontrado"))?;
+
+    if let Some(day) = body.day {
+        slot.day = day;
+    }
+    if let Some(start_time) = body.start_time {
+        slot.start_time = start_time;
+    }
+    if let Some(end_time) = body.end_time {
+        slot.end_time = end_time;
+    }
+    if let Some(space_id) = body.space_id {
+        slot.space_id = space_id;
+    }
+
+    let mut tx = state.pool.begin().await?;
+    check_availability(
+        &mut tx,
+        slot.space_id,
+        slot.day,
+        slot.start_time,
+        slot.end_time,
+        Some(slot.id),
+    )
+    .await?;
+
+    let slot = ScheduleRepository::update(&mut tx, &slot)
+        .await
+        .map_err(map_space_error)?;
+    tx.commit().await?;
+    Ok(Json(ApiResponse::new(slot)))
+}
+
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ApiResponse<Uuid>>, AppError> {
+    if !ScheduleRepository::delete(&state.pool, uuid).await? {
+        return Err(AppError::NotFound("Horario no encontrado"));
+    }
+    Ok(Json(ApiResponse::new(uuid)))
+}
+
+// Rechaza la franja si se superpone con otra actividad o con un alquiler futuro del mismo espacio
+async fn check_availability(
+    conn: &mut PgConnection,
+    space_id: Uuid,
+    day: Weekday,
+    start_time: NaiveTime,
+    end_time: NaiveTime,
+    exclude_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    if end_time <= start_time {
+        return Err(AppError::BadRequest(
+            "La hora de fin debe ser posterior a la de inicio".to_string(),
+        ));
+    }
+
+    let space = CreateSpaceRepository::find_by_id_for_update(&mut *conn, space_id)
+        .await?
+        .ok_or(AppError::BadRequest(
+            "El espacio indicado no existe".to_string(),
+        ))?;
+    if space.retired_at.is_some() {
+        return Err(AppError::BadRequest(format!(
+            "El espacio \"{}\" fue dado de baja",
+            space.name
+        )));
+    }
+
+    let slots = ScheduleRepository::find_overlapping_slots(
+        &mut *conn, space_id, day, start_time, end_time, exclude_id,
+    )
+    .await?;
+    if let Some(slot) = slots.first() {
+        return Err(AppError::Conflict(format!(
+            "{} ya ocupa \"{}\" de {} a {}",
+            slot.activity_name,
+            slot.space_name,
+            slot.start_time.format("%H:%M"),
+            slot.end_time.format("%H:%M")
+        )));
+    }
+
+    let rents = RentRepository::find_overlapping_on_weekday(
+        conn,
+        space_id,
+        day,
+        start_time,
+        end_time,
+        dates::today(),
+    )
+    .await?;
+    if let Some(rent) = rents.first() {
+        return Err(AppError::Conflict(format!(
+            "El espacio está alquilado por {} el {} de {} a {}",
+            rent.full_name,
+            rent.rent_date.format("%d/%m/%Y"),
+            rent.start_time.format("%H:%M"),
+            rent.end_time.format("%H:%M")
+        )));
+    }
+
+    Ok(())
+}
+
+fn map_space_error(e: sqlx::Error) -> AppError {
+    AppError::from(e).on_invalid_reference("El espacio indicado no existe")
+}
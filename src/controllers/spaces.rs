@@ -1,48 +1,87 @@
-use axum::{
-    body::{self, Body},
-    extract::{Query, State},
-    http::{status, Response, StatusCode},
-    response::IntoResponse,
-    Json,
-};
-use chrono::{NaiveDate, NaiveDateTime};
+use axum::extract::State;
+use chrono::{Days, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, FieldError},
+    helpers::{
+        dates,
+        extract::{Json, Path, Query},
+        intervals, nullable,
+    },
+    models::{
+        schedule::Weekday,
+        space::{default_opening_hours, OpeningHours, Space},
+    },
+    repository::{
+        rent::RentRepository,
+        schedule::ScheduleRepository,
+        space::{CreateSpaceRepository, SpaceData},
+    },
+    utils::AppState,
+};
 
-use crate::{repository::space::CreateSpaceRepository, utils::AppState};
+use super::users::ApiResponse;
+
+// Rango máximo de días que se puede consultar de una vez
+const MAX_AVAILABILITY_DAYS: u64 = 62;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ResponseSpace {
     pub id: String,
     pub name: String,
+    pub capacity: Option<i32>,
+    pub is_indoor: bool,
+    pub hourly_price: Option<Decimal>,
+    pub retired_at: Option<NaiveDateTime>,
+    pub opening_hours: Vec<OpeningHours>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum SpaceError {
-    NotFound,
-    InternalServerError(String),
-}
-
-impl IntoResponse for SpaceError {
-    fn into_response(self) -> Response<Body> {
-        let (status, message) = match self {
-            SpaceError::NotFound => (StatusCode::NOT_FOUND, "Space not found".to_string()),
-            SpaceError::InternalServerError(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
-        };
-
-        let body = Json(json!({
-            "status": "error",
-            "error": message,
-        }));
-        (status, body).into_response()
+impl ResponseSpace {
+    pub fn new(space: Space, opening_hours: Vec<OpeningHours>) -> Self {
+        Self {
+            id: space.id.to_string(),
+            name: space.name,
+            capacity: space.capacity,
+            is_indoor: space.is_indoor,
+            hourly_price: space.hourly_price,
+            retired_at: space.retired_at,
+            opening_hours,
+            created_at: space.created_at,
+            updated_at: space.updated_at,
+        }
     }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SpaceCreateRequest {
     name: String,
+    capacity: Option<i32>,
+    #[serde(default = "default_is_indoor")]
+    is_indoor: bool,
+    hourly_price: Option<Decimal>,
+    opening_hours: Option<Vec<OpeningHours>>,
+}
+
+fn default_is_indoor() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpaceUpdateRequest {
+    name: Option<String>,
+    // null borra la capacidad o la tarifa; si el campo no viene se mantiene el valor
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    capacity: Option<Option<i32>>,
+    is_indoor: Option<bool>,
+    #[serde(default, deserialize_with = "nullable::deserialize")]
+    hourly_price: Option<Option<Decimal>>,
+    opening_hours: Option<Vec<OpeningHours>>,
 }
 
 #[derive(Deserialize)]
@@ -50,42 +89,386 @@ pub struct SpaceQuery {
     name: String,
 }
 
+#[derive(Deserialize)]
+pub struct SpaceListQuery {
+    #[serde(default)]
+    include_retired: bool,
+}
+
 pub async fn create(
     State(state): State<AppState>,
     Json(body): Json<SpaceCreateRequest>,
-) -> Result<Json<ResponseSpace>, SpaceError> {
-    if CreateSpaceRepository::find_by_name(&state.pool, &body.name)
-        .await
+) -> Result<Json<ResponseSpace>, AppError> {
+    let data = SpaceData {
+        name: body.name.trim().to_string(),
+        capacity: body.capacity,
+        is_indoor: body.is_indoor,
+        hourly_price: body.hourly_price,
+    };
+    let opening_hours = body.opening_hours.unwrap_or_else(default_opening_hours);
+    validate(&data, &opening_hours)?;
+
+    if CreateSpaceRepository::find_by_name(&state.pool, &data.name)
+        .await?
         .is_some()
     {
-        return Err(SpaceError::InternalServerError(format!(
+        return Err(AppError::AlreadyExists(format!(
             "Space \"{}\" already exists",
-            &body.name
+            &data.name
         )));
     }
 
-    match CreateSpaceRepository::create(&state.pool, &body.name).await {
-        Ok(space) => Ok(Json(ResponseSpace {
-            id: space.id.to_string(),
-            name: space.name,
-            created_at: space.created_at,
-            updated_at: space.updated_at,
-        })),
-        Err(e) => Err(SpaceError::InternalServerError(e.to_string())),
-    }
+    let space = CreateSpaceRepository::create(&state.pool, &data, &opening_hours).await?;
+    let opening_hours = CreateSpaceRepository::find_opening_hours(&state.pool, space.id).await?;
+    Ok(Json(ResponseSpace::new(space, opening_hours)))
 }
 
 pub async fn find_by_name(
     State(state): State<AppState>,
     Query(query): Query<SpaceQuery>,
-) -> Result<Json<ResponseSpace>, SpaceError> {
-    match CreateSpaceRepository::find_by_name(&state.pool, &query.name).await {
-        Some(space) => Ok(Json(ResponseSpace {
-            id: space.id.to_string(),
-            name: space.name,
-            created_at: space.created_at,
-            updated_at: space.updated_at,
-        })),
-        None => Err(SpaceError::NotFound),
+) -> Result<Json<ResponseSpace>, AppError> {
+    match CreateSpaceRepository::find_by_name(&state.pool, &query.name).await? {
+        Some(space) => {
+            let opening_hours =
+                CreateSpaceRepository::find_opening_hours(&state.pool, space.id).await?;
+            Ok(Json(ResponseSpace::new(space, opening_hours)))
+        }
+        None => Err(AppError::NotFound("Espacio no encontrado")),
+    }
+}
+
+pub async fn find_all(
+    State(state): State<AppState>,
+    Query(query): Query<SpaceListQuery>,
+) -> Result<Json<ApiResponse<Vec<ResponseSpace>>>, AppError> {
+    let spaces = CreateSpaceRepository::find_all(&state.pool, query.include_retired).await?;
+    let mut response = Vec::with_capacity(spaces.len());
+    for space in spaces {
+        let opening_hours =
+            CreateSpaceRepository::find_opening_hours(&state.pool, space.id).await?;
+        response.push(ResponseSpace::new(space, opening_hours));
+    }
+    Ok(Json(ApiResponse::new(response)))
+}
+
+pub async fn find_one(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ResponseSpace>, AppError> {
+    let space = CreateSpaceRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Espacio no encontrado"))?;
+    let opening_hours = CreateSpaceRepository::find_opening_hours(&state.pool, uuid).await?;
+    Ok(Json(ResponseSpace::new(space, opening_hours)))
+}
+
+pub async fn update(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Json(body): Json<SpaceUpdateRequest>,
+) -> Result<Json<ResponseSpace>, AppError> {
+    // El espacio queda bloqueado para que no se agenden horarios ni alquileres mientras se
+    // controla el horario nuevo
+    let mut tx = state.pool.begin().await?;
+    let space = CreateSpaceRepository::find_by_id_for_update(&mut tx, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Espacio no encontrado"))?;
+
+    let data = SpaceData {
+        name: body
+            .name
+            .map(|name| name.trim().to_string())
+            .unwrap_or(space.name),
+        capacity: body.capacity.unwrap_or(space.capacity),
+        is_indoor: body.is_indoor.unwrap_or(space.is_indoor),
+        hourly_price: body.hourly_price.unwrap_or(space.hourly_price),
+    };
+    let opening_hours = match &body.opening_hours {
+        Some(opening_hours) => opening_hours.clone(),
+        None => CreateSpaceRepository::find_opening_hours(&state.pool, uuid).await?,
+    };
+    validate(&data, &opening_hours)?;
+    if body.opening_hours.is_some() {
+        check_opening_hours(&mut tx, uuid, &opening_hours).await?;
+    }
+
+    if let Some(existing) = CreateSpaceRepository::find_by_name(&state.pool, &data.name).await? {
+        if existing.id != uuid {
+            return Err(AppError::AlreadyExists(format!(
+                "Space \"{}\" already exists",
+                &data.name
+            )));
+        }
     }
+
+    let space =
+        CreateSpaceRepository::update(&mut tx, uuid, &data, body.opening_hours.as_deref()).await?;
+    tx.commit().await?;
+    Ok(Json(ResponseSpace::new(space, opening_hours)))
+}
+
+// Rechaza un horario nuevo que deje afuera horarios de actividades o alquileres futuros,
+// indicando cuáles hay que mover antes
+async fn check_opening_hours(
+    conn: &mut PgConnection,
+    space_id: Uuid,
+    opening_hours: &[OpeningHours],
+) -> Result<(), AppError> {
+    let fits = |day: Weekday, start_time: NaiveTime, end_time: NaiveTime| {
+        opening_hours.iter().any(|hours| {
+            hours.day == day && hours.opens_at <= start_time && end_time <= hours.closes_at
+        })
+    };
+
+    let mut conflicts = vec![];
+    for slot in ScheduleRepository::find_by_space(conn, space_id).await? {
+        if !fits(slot.day, slot.start_time, slot.end_time) {
+            conflicts.push(format!(
+                "{} el {:?} de {} a {}",
+                slot.activity_name, slot.day, slot.start_time, slot.end_time
+            ));
+        }
+    }
+    for rent in RentRepository::find_upcoming_by_space(conn, space_id, dates::today()).await? {
+        if !fits(Weekday::of(rent.rent_date), rent.start_time, rent.end_time) {
+            conflicts.push(format!(
+                "alquiler del {} de {} a {}",
+                rent.rent_date.format("%d/%m/%Y"),
+                rent.start_time,
+                rent.end_time
+            ));
+        }
+    }
+
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Conflict(format!(
+            "El nuevo horario deja fuera: {}. Reubíquelos o cancélelos antes de cambiarlo",
+            conflicts.join("; ")
+        )))
+    }
+}
+
+// No se puede dar de baja un espacio que todavía tiene horarios o alquileres futuros. Se
+// controla con el espacio bloqueado, igual que al crear horarios y alquileres, para que no
+// aparezca uno nuevo entre el control y la baja
+pub async fn retire(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+) -> Result<Json<ResponseSpace>, AppError> {
+    let mut tx = state.pool.begin().await?;
+    CreateSpaceRepository::find_by_id_for_update(&mut tx, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Espacio no encontrado"))?;
+    let (slots, rents) =
+        CreateSpaceRepository::count_references(&mut tx, uuid, dates::today()).await?;
+    if slots > 0 || rents > 0 {
+        return Err(AppError::Conflict(format!(
+            "El espacio tiene {} horarios de actividades y {} alquileres futuros; reubíquelos o cancélelos antes de darlo de baja",
+            slots, rents
+        )));
+    }
+
+    let space = CreateSpaceRepository::retire(&mut tx, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Espacio no encontrado"))?;
+    tx.commit().await?;
+    let opening_hours = CreateSpaceRepository::find_opening_hours(&state.pool, uuid).await?;
+    Ok(Json(ResponseSpace::new(space, opening_hours)))
+}
+
+fn validate(data: &SpaceData, opening_hours: &[OpeningHours]) -> Result<(), AppError> {
+    let mut errors = vec![];
+
+    if data.name.is_empty() {
+        errors.push(FieldError::new(
+            "name",
+            "El nombre del espacio es obligatorio",
+        ));
+    }
+    if matches!(data.capacity, Some(capacity) if capacity <= 0) {
+        errors.push(FieldError::new(
+            "capacity",
+            "La capacidad debe ser mayor a cero",
+        ));
+    }
+    if matches!(data.hourly_price, Some(price) if price < Decimal::ZERO) {
+        errors.push(FieldError::new(
+            "hourly_price",
+            "La tarifa por hora no puede ser negativa",
+        ));
+    }
+    for (index, hours) in opening_hours.iter().enumerate() {
+        if hours.closes_at <= hours.opens_at {
+            errors.push(FieldError::new(
+                "opening_hours",
+                format!(
+                    "El horario de cierre del {:?} debe ser posterior al de apertura",
+                    hours.day
+                ),
+            ));
+        }
+        if opening_hours[..index]
+            .iter()
+            .any(|other| other.day == hours.day)
+        {
+            errors.push(FieldError::new(
+                "opening_hours",
+                format!("El día {:?} figura más de una vez en el horario", hours.day),
+            ));
+        }
+    }
+
+    AppError::validate(errors)
+}
+
+#[derive(Deserialize)]
+pub struct AvailabilityQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpaceAvailability {
+    pub space_id: Uuid,
+    pub space_name: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub days: Vec<DayAvailability>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayAvailability {
+    pub date: NaiveDate,
+    pub day: Weekday,
+    // None si el espacio está cerrado ese día
+    pub opens_at: Option<NaiveTime>,
+    pub closes_at: Option<NaiveTime>,
+    pub busy: Vec<BusyInterval>,
+    pub free: Vec<FreeInterval>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BusyKind {
+    Activity,
+    Rent,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BusyInterval {
+    pub kind: BusyKind,
+    pub id: Uuid,
+    pub label: String,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FreeInterval {
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+// Horarios de actividades y alquileres del espacio, con los huecos libres de cada día
+pub async fn availability(
+    State(state): State<AppState>,
+    Path(uuid): Path<Uuid>,
+    Query(query): Query<AvailabilityQuery>,
+) -> Result<Json<ApiResponse<SpaceAvailability>>, AppError> {
+    let from = query.from.unwrap_or_else(dates::today);
+    let to = match query.to {
+        Some(to) => to,
+        None => from
+            .checked_add_days(Days::new(6))
+            .ok_or(AppError::Validation(vec![FieldError::new(
+                "from",
+                "La fecha está fuera del rango admitido",
+            )]))?,
+    };
+    if to < from {
+        return Err(AppError::BadRequest(
+            "La fecha final debe ser posterior a la inicial".to_string(),
+        ));
+    }
+    if (to - from).num_days() > MAX_AVAILABILITY_DAYS as i64 {
+        return Err(AppError::BadRequest(format!(
+            "El rango no puede superar los {} días",
+            MAX_AVAILABILITY_DAYS
+        )));
+    }
+
+    let space = CreateSpaceRepository::find_by_id(&state.pool, uuid)
+        .await?
+        .ok_or(AppError::NotFound("Espacio no encontrado"))?;
+    let slots = ScheduleRepository::find_by_space(&mut *state.pool.acquire().await?, uuid).await?;
+    let rents =
+        RentRepository::find_all(&state.pool, Some(uuid), Some(from), Some(to), false).await?;
+
+    let opening_hours = CreateSpaceRepository::find_opening_hours(&state.pool, uuid).await?;
+    let days = from
+        .iter_days()
+        .take_while(|date| *date <= to)
+        .map(|date| {
+            let day = Weekday::of(date);
+            let mut busy: Vec<BusyInterval> = slots
+                .iter()
+                .filter(|slot| slot.day == day)
+                .map(|slot| BusyInterval {
+                    kind: BusyKind::Activity,
+                    id: slot.id,
+                    label: slot.activity_name.clone(),
+                    start_time: slot.start_time,
+                    end_time: slot.end_time,
+                })
+                .chain(
+                    rents
+                        .iter()
+                        .filter(|rent| rent.rent_date == date)
+                        .map(|rent| BusyInterval {
+                            kind: BusyKind::Rent,
+                            id: rent.id,
+                            // La disponibilidad es pública: no se expone quién alquila
+                            label: "Alquilado".to_string(),
+                            start_time: rent.start_time,
+                            end_time: rent.end_time,
+                        }),
+                )
+                .collect();
+            busy.sort_by_key(|interval| interval.start_time);
+
+            let ranges: Vec<(NaiveTime, NaiveTime)> = busy
+                .iter()
+                .map(|interval| (interval.start_time, interval.end_time))
+                .collect();
+            let hours = opening_hours.iter().find(|hours| hours.day == day);
+            let free = hours
+                .map(|hours| intervals::free_gaps(hours.opens_at, hours.closes_at, &ranges))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(start_time, end_time)| FreeInterval {
+                    start_time,
+                    end_time,
+                })
+                .collect();
+
+            DayAvailability {
+                date,
+                day,
+                opens_at: hours.map(|hours| hours.opens_at),
+                closes_at: hours.map(|hours| hours.closes_at),
+                busy,
+                free,
+            }
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::new(SpaceAvailability {
+        space_id: space.id,
+        space_name: space.name,
+        from,
+        to,
+        days,
+    })))
 }
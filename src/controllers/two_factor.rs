@@ -0,0 +1,133 @@
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::AuthError,
+    helpers::{
+        extract::Json,
+        tokens::{self, hash_token},
+        totp,
+    },
+    middlewares::auth::AuthUser,
+    repository::{role::RoleRepository, two_factor::TwoFactorRepository},
+    utils::AppState,
+};
+
+use super::users::{normalize_recovery_code, ApiResponse};
+
+const RECOVERY_CODE_COUNT: usize = 10;
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetup {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+// Los códigos de recuperación solo se muestran una vez
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+// Genera un secreto nuevo; queda pendiente hasta confirmarlo con un código
+pub async fn setup(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<ApiResponse<TwoFactorSetup>>, AuthError> {
+    if auth_user.user.totp_enabled_at.is_some() {
+        return Err(AuthError::TwoFactorAlreadyEnabled);
+    }
+
+    let secret = totp::generate_secret();
+    let otpauth_uri = totp::otpauth_uri(&secret, &auth_user.user.email).ok_or(AuthError::Other(
+        "No se pudo generar la URI otpauth".to_string(),
+    ))?;
+    TwoFactorRepository::set_pending_secret(&state.pool, auth_user.user.id, &secret)
+        .await
+        .map_err(AuthError::DatabaseError)?;
+
+    Ok(Json(ApiResponse::new(TwoFactorSetup {
+        secret,
+        otpauth_uri,
+    })))
+}
+
+pub async fn confirm(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<ApiResponse<RecoveryCodes>>, AuthError> {
+    if auth_user.user.totp_enabled_at.is_some() {
+        return Err(AuthError::TwoFactorAlreadyEnabled);
+    }
+    let secret = auth_user
+        .user
+        .totp_secret
+        .as_deref()
+        .ok_or(AuthError::TwoFactorNotEnabled)?;
+    let step = totp::verify(secret, &payload.code).ok_or(AuthError::InvalidTwoFactorCode)?;
+
+    let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let token = tokens::generate_token();
+            format!("{}-{}", &token[..5], &token[5..10])
+        })
+        .collect();
+    let hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| hash_token(&normalize_recovery_code(code)))
+        .collect();
+
+    let mut tx = state.pool.begin().await.map_err(AuthError::DatabaseError)?;
+    if !TwoFactorRepository::accept_step(&mut tx, auth_user.user.id, step)
+        .await
+        .map_err(AuthError::DatabaseError)?
+    {
+        return Err(AuthError::InvalidTwoFactorCode);
+    }
+    TwoFactorRepository::enable(&mut tx, auth_user.user.id, &hashes)
+        .await
+        .map_err(AuthError::DatabaseError)?;
+    tx.commit().await.map_err(AuthError::DatabaseError)?;
+
+    Ok(Json(ApiResponse::new(RecoveryCodes { recovery_codes })))
+}
+
+pub async fn disable(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<ApiResponse<String>>, AuthError> {
+    let secret = match (&auth_user.user.totp_enabled_at, &auth_user.user.totp_secret) {
+        (Some(_), Some(secret)) => secret,
+        _ => return Err(AuthError::TwoFactorNotEnabled),
+    };
+    if RoleRepository::requires_two_factor(&state.pool, &auth_user.user.rolename)
+        .await
+        .map_err(AuthError::DatabaseError)?
+    {
+        return Err(AuthError::TwoFactorRequiredByRole);
+    }
+    let step = totp::verify(secret, &payload.code).ok_or(AuthError::InvalidTwoFactorCode)?;
+
+    let mut tx = state.pool.begin().await.map_err(AuthError::DatabaseError)?;
+    if !TwoFactorRepository::accept_step(&mut tx, auth_user.user.id, step)
+        .await
+        .map_err(AuthError::DatabaseError)?
+    {
+        return Err(AuthError::InvalidTwoFactorCode);
+    }
+    TwoFactorRepository::disable(&mut tx, auth_user.user.id)
+        .await
+        .map_err(AuthError::DatabaseError)?;
+    tx.commit().await.map_err(AuthError::DatabaseError)?;
+
+    Ok(Json(ApiResponse::new(
+        "Verificación en dos pasos desactivada".to_string(),
+    )))
+}
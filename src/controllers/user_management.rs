@@ -0,0 +1,367 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppError,
+    helpers::{
+        client_ip::client_ip,
+        extract::{Json, Path},
+        hash_password,
+        tokens::{self, hash_token},
+    },
+    mail::MailMessage,
+    middlewares::auth::AuthUser,
+    repository::{
+        login_attempt::{LoginAttempt, LoginAttemptRepository, LoginLockout},
+        password_token::PasswordTokenRepository,
+        refresh_token::RefreshTokenRepository,
+        role::{Role, RoleRepository},
+        two_factor::TwoFactorRepository,
+        UserRepository,
+    },
+    utils::{AppState, User},
+};
+
+use super::users::ApiResponse;
+
+// Vida del token de invitación (7 días), del restablecimiento forzado (24 horas) y del pedido por el usuario (1 hora)
+const INVITE_TOKEN_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+const RESET_TOKEN_TTL_SECS: i64 = 24 * 60 * 60;
+const FORGOT_TOKEN_TTL_SECS: i64 = 60 * 60;
+// Pedidos de restablecimiento permitidos por hora para un mismo correo y una misma IP
+const FORGOT_WINDOW_SECS: i64 = 60 * 60;
+const FORGOT_MAX_PER_EMAIL: i32 = 3;
+const FORGOT_MAX_PER_IP: i32 = 20;
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+#[derive(Debug, Deserialize)]
+pub struct InviteRequest {
+    pub name: String,
+    pub email: String,
+    pub rolename: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleRequest {
+    pub rolename: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
+// Token de un solo uso para que el usuario defina su contraseña
+#[derive(Debug, Serialize)]
+pub struct PasswordTokenResponse {
+    pub user: User,
+    pub token: String,
+    pub expires_at: NaiveDateTime,
+}
+
+pub async fn find_all(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<User>>>, AppError> {
+    let users = UserRepository::find_all(&state.pool).await?;
+    Ok(Json(ApiResponse::new(users)))
+}
+
+pub async fn invite(
+    State(state): State<AppState>,
+    Json(payload): Json<InviteRequest>,
+) -> Result<(StatusCode, Json<ApiResponse<PasswordTokenResponse>>), AppError> {
+    let name = payload.name.trim();
+    let email = payload.email.trim().to_lowercase();
+    if name.is_empty() || !email.contains('@') {
+        return Err(AppError::BadRequest(
+            "El nombre y un correo válido son obligatorios".to_string(),
+        ));
+    }
+
+    let mut tx = state.pool.begin().await?;
+    let user = UserRepository::create_invited(&mut tx, name, &email, &payload.rolename)
+        .await
+        .map_err(|e| {
+            AppError::from(e)
+                .on_unique_violation("El correo ya está registrado")
+                .on_invalid_reference("El rol indicado no existe")
+        })?;
+    let (token, expires_at) =
+        create_password_token(&mut tx, user.id, "invite", INVITE_TOKEN_TTL_SECS).await?;
+    tx.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::new(PasswordTokenResponse {
+            user,
+            token,
+            expires_at,
+        })),
+    ))
+}
+
+pub async fn update_role(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<RoleRequest>,
+) -> Result<Json<ApiResponse<User>>, AppError> {
+    // Evita que un administrador se quite el acceso a sí mismo
+    if auth_user.user.id == id {
+        return Err(AppError::BadRequest(
+            "No podés cambiar tu propio rol".to_string(),
+        ));
+    }
+
+    let user = UserRepository::update_role(&state.pool, id, &payload.rolename)
+        .await
+        .map_err(|e| AppError::from(e).on_invalid_reference("El rol indicado no existe"))?
+        .ok_or(AppError::NotFound("Usuario no encontrado"))?;
+    Ok(Json(ApiResponse::new(user)))
+}
+
+pub async fn disable(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<User>>, AppError> {
+    if auth_user.user.id == id {
+        return Err(AppError::BadRequest(
+            "No podés deshabilitar tu propia cuenta".to_string(),
+        ));
+    }
+
+    // Además de marcar la cuenta se cierran todas sus sesiones
+    let mut tx = state.pool.begin().await?;
+    let user = UserRepository::set_disabled(&mut tx, id, true)
+        .await?
+        .ok_or(AppError::NotFound("Usuario no encontrado"))?;
+    RefreshTokenRepository::revoke_all_for_user(&mut tx, id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::new(user)))
+}
+
+pub async fn enable(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<User>>, AppError> {
+    let mut tx = state.pool.begin().await?;
+    let user = UserRepository::set_disabled(&mut tx, id, false)
+        .await?
+        .ok_or(AppError::NotFound("Usuario no encontrado"))?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::new(user)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoleTwoFactorRequest {
+    pub require_two_factor: bool,
+}
+
+pub async fn find_roles(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<Role>>>, AppError> {
+    let roles = RoleRepository::find_all(&state.pool).await?;
+    Ok(Json(ApiResponse::new(roles)))
+}
+
+// Exige (o deja de exigir) verificación en dos pasos a todos los usuarios del rol
+pub async fn update_role_two_factor(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<RoleTwoFactorRequest>,
+) -> Result<Json<ApiResponse<Vec<Role>>>, AppError> {
+    if !RoleRepository::set_require_two_factor(&state.pool, &name, payload.require_two_factor)
+        .await?
+    {
+        return Err(AppError::NotFound("Rol no encontrado"));
+    }
+
+    let roles = RoleRepository::find_all(&state.pool).await?;
+    Ok(Json(ApiResponse::new(roles)))
+}
+
+// Para quien perdió el dispositivo y los códigos de recuperación: vuelve a enrolarse al iniciar sesión
+pub async fn reset_two_factor(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<User>>, AppError> {
+    let mut tx = state.pool.begin().await?;
+    TwoFactorRepository::disable(&mut tx, id).await?;
+    RefreshTokenRepository::revoke_all_for_user(&mut tx, id).await?;
+    tx.commit().await?;
+
+    let user = UserRepository::find_by_id(&state.pool, id)
+        .await?
+        .ok_or(AppError::NotFound("Usuario no encontrado"))?;
+    Ok(Json(ApiResponse::new(user)))
+}
+
+#[derive(Debug, Serialize)]
+pub struct LockoutsResponse {
+    pub active: Vec<LoginAttempt>,
+    pub history: Vec<LoginLockout>,
+}
+
+// Bloqueos vigentes y los últimos registrados
+pub async fn find_lockouts(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<LockoutsResponse>>, AppError> {
+    let active = LoginAttemptRepository::find_active_lockouts(&state.pool).await?;
+    let history = LoginAttemptRepository::find_lockout_history(&state.pool, 100).await?;
+    Ok(Json(ApiResponse::new(LockoutsResponse { active, history })))
+}
+
+pub async fn clear_lockout(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<LoginAttempt>>, AppError> {
+    let attempt = LoginAttemptRepository::clear(&state.pool, id, &auth_user.user.email)
+        .await?
+        .ok_or(AppError::NotFound("Bloqueo no encontrado"))?;
+    Ok(Json(ApiResponse::new(attempt)))
+}
+
+// Invalida la contraseña actual y las sesiones abiertas, y emite un token para definir una nueva
+pub async fn force_password_reset(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<PasswordTokenResponse>>, AppError> {
+    let user = UserRepository::find_by_id(&state.pool, id)
+        .await?
+        .ok_or(AppError::NotFound("Usuario no encontrado"))?;
+
+    let mut tx = state.pool.begin().await?;
+    UserRepository::update_password(&mut tx, id, "").await?;
+    RefreshTokenRepository::revoke_all_for_user(&mut tx, id).await?;
+    let (token, expires_at) =
+        create_password_token(&mut tx, id, "reset", RESET_TOKEN_TTL_SECS).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::new(PasswordTokenResponse {
+        user,
+        token,
+        expires_at,
+    })))
+}
+
+// Envía por correo un enlace para restablecer la contraseña. La respuesta es la misma y tarda
+// lo mismo exista o no la cuenta (el envío va en segundo plano), para no revelar qué correos
+// están registrados. Los pedidos de más se descartan en silencio por el mismo motivo
+pub async fn forgot_password(
+    State(state): State<AppState>,
+    request_headers: HeaderMap,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let email = payload.email.trim().to_lowercase();
+
+    let mut limits = vec![("reset_email", email.clone(), FORGOT_MAX_PER_EMAIL)];
+    if let Some(ip) = client_ip(&request_headers, state.trusted_proxy_hops) {
+        limits.push(("reset_ip", ip, FORGOT_MAX_PER_IP));
+    }
+    let mut throttled = false;
+    for (kind, key, max) in &limits {
+        let attempt =
+            LoginAttemptRepository::record_failure(&state.pool, kind, key, FORGOT_WINDOW_SECS)
+                .await?;
+        throttled |= attempt.failed_count > *max;
+    }
+
+    if !throttled {
+        tokio::spawn(async move {
+            if let Err(e) = send_reset_link(&state, &email).await {
+                tracing::warn!("Error al enviar el enlace de restablecimiento: {:?}", e);
+            }
+        });
+    }
+
+    Ok(Json(ApiResponse::new(
+        "Si el correo está registrado, recibirás un enlace para restablecer la contraseña"
+            .to_string(),
+    )))
+}
+
+async fn send_reset_link(state: &AppState, email: &str) -> Result<(), AppError> {
+    let user = UserRepository::find_by_email(State(state.clone()), email).await?;
+    let Some(user) = user.filter(|user| user.disabled_at.is_none()) else {
+        return Ok(());
+    };
+
+    let mut tx = state.pool.begin().await?;
+    let (token, _) =
+        create_password_token(&mut tx, user.id, "reset", FORGOT_TOKEN_TTL_SECS).await?;
+    tx.commit().await?;
+
+    let message = MailMessage {
+        to: user.email.clone(),
+        subject: "Restablecer contraseña".to_string(),
+        body: format!(
+            "Hola {},\n\nPara elegir una nueva contraseña ingresá a:\n{}/reset-password?token={}\n\nEl enlace vence en una hora. Si no lo pediste, ignorá este correo.",
+            user.name, state.frontend_url, token
+        ),
+    };
+    if let Err(e) = state.mailer.send(message).await {
+        tracing::warn!("Error al enviar el correo a {}: {}", user.email, e);
+    }
+    Ok(())
+}
+
+// Canjea un token de invitación o restablecimiento por una contraseña nueva
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    if payload.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "La contraseña debe tener al menos {} caracteres",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+
+    let mut tx = state.pool.begin().await?;
+    let stored =
+        PasswordTokenRepository::find_by_hash_for_update(&mut tx, &hash_token(&payload.token))
+            .await?
+            .filter(|token| {
+                token.used_at.is_none() && token.expires_at >= chrono::Local::now().naive_local()
+            })
+            .ok_or(AppError::BadRequest(
+                "El enlace es inválido o ya venció".to_string(),
+            ))?;
+
+    let password = hash_password::hash_password(payload.password)
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    UserRepository::update_password(&mut tx, stored.user_id, &password).await?;
+    RefreshTokenRepository::revoke_all_for_user(&mut tx, stored.user_id).await?;
+    PasswordTokenRepository::mark_used(&mut tx, stored.id).await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::new("Contraseña actualizada".to_string())))
+}
+
+async fn create_password_token(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+    purpose: &str,
+    ttl_secs: i64,
+) -> Result<(String, NaiveDateTime), AppError> {
+    let token = tokens::generate_token();
+    let expires_at = chrono::Local::now().naive_local() + chrono::Duration::seconds(ttl_secs);
+    PasswordTokenRepository::create(conn, user_id, &hash_token(&token), purpose, expires_at)
+        .await?;
+    Ok((token, expires_at))
+}
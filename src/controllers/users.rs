@@ -1,17 +1,59 @@
 use std::time::SystemTime;
 
-use axum::{extract::State, http::HeaderMap, Json};
-use cookie::{Cookie, SameSite};
+use axum::{
+    extract::State,
+    http::{header::SET_COOKIE, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use chrono::NaiveDateTime;
 use jsonwebtoken::{encode, Header};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::{
-    errors::AuthError,
-    helpers,
-    repository::UserRepository,
+    errors::{AppError, AuthError},
+    helpers::{
+        self,
+        client_ip::client_ip,
+        cookies::{self, ACCESS_TOKEN_COOKIE, REFRESH_TOKEN_COOKIE, REFRESH_TOKEN_PATH},
+        extract::Json,
+        tokens::{self, hash_token},
+        totp,
+    },
+    repository::{
+        login_attempt::LoginAttemptRepository, refresh_token::RefreshTokenRepository,
+        two_factor::TwoFactorRepository, UserRepository,
+    },
     utils::{AppState, AuthBody, AuthRequestPayload, Claims, User},
 };
 
+// Vida del JWT de acceso (15 minutos) y del token de refresco (30 días)
+const ACCESS_TOKEN_TTL_SECS: i64 = 15 * 60;
+const REFRESH_TOKEN_TTL_SECS: i64 = 30 * 24 * 60 * 60;
+
+// Los fallos se olvidan tras 15 minutos sin intentos; el bloqueo dura 15 minutos
+const LOGIN_WINDOW_SECS: i64 = 15 * 60;
+const LOCKOUT_SECS: i64 = 15 * 60;
+const MAX_BACKOFF_SECS: i64 = 60;
+
+// El segundo paso del login debe completarse en 5 minutos y admite 5 intentos
+const TWO_FACTOR_CHALLENGE_TTL_SECS: i64 = 5 * 60;
+const MAX_TWO_FACTOR_ATTEMPTS: i32 = 5;
+
+#[derive(Serialize)]
+pub struct TwoFactorChallengeBody {
+    pub status: String,
+    pub challenge_token: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Deserialize)]
+pub struct TwoFactorLoginPayload {
+    pub challenge_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct RegisterRequestPayload {
     pub name: String,
@@ -22,101 +64,436 @@ pub struct RegisterRequestPayload {
 pub async fn register(
     State(state): State<AppState>,
     Json(payload): Json<RegisterRequestPayload>,
-) -> Result<Json<AuthBody>, AuthError> {
+) -> Result<(HeaderMap, Json<AuthBody>), AppError> {
+    if !state.allow_registration {
+        return Err(AuthError::RegistrationDisabled.into());
+    }
+
     // Check if the user sent the credentials
-    let existing_user = UserRepository::find_by_email(State(state.clone()), &payload.email).await;
+    let existing_user = UserRepository::find_by_email(State(state.clone()), &payload.email).await?;
 
     if existing_user.is_some() {
-        return Err(AuthError::Other("User already exists".to_string()));
+        return Err(AppError::AlreadyExists("El usuario ya existe".to_string()));
     }
 
     let new_user = User {
+        id: Uuid::new_v4(),
         name: payload.name.clone(),
         rolename: "user".to_string(),
         email: payload.email.clone(),
         password: helpers::hash_password::hash_password(payload.password.clone())
             .map_err(|e| AuthError::HashingError(e.to_string()))?,
+        disabled_at: None,
+        totp_secret: None,
+        totp_enabled_at: None,
         created_at: chrono::Local::now().naive_local(),
         updated_at: chrono::Local::now().naive_local(),
     };
 
-    UserRepository::save_user(State(state.clone()), new_user.clone()).await;
-    // add 4 minutes to current unix epoch time as expiry date/time
-    let exp = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        + 299;
-
-    // Create the authorization token
-    let encoding_jwt = &state.jwt_secret.encoding;
-
-    let claims = Claims {
-        sub: new_user.email.clone(),
-        exp,
-        role: "user".to_string(),
-    };
-    let token =
-        encode(&Header::default(), &claims, encoding_jwt).map_err(|_| AuthError::TokenCreation)?;
+    UserRepository::save_user(State(state.clone()), new_user.clone()).await?;
 
-    set_cookie("accessToken", token);
+    let headers = issue_session(&state, &new_user).await?;
 
     // Send the authorized token
-    Ok(Json(AuthBody::new(new_user)))
+    Ok((headers, Json(AuthBody::new(new_user))))
 }
 
 pub async fn login(
     State(state): State<AppState>,
+    request_headers: HeaderMap,
     Json(payload): Json<AuthRequestPayload>,
-) -> Result<Json<AuthBody>, AuthError> {
+) -> Result<Response, AuthError> {
     // Check if the user sent the credentials
     if payload.email.is_empty() || payload.password.is_empty() {
         return Err(AuthError::MissingCredentials);
     }
-    let user = match UserRepository::find_by_email(State(state.clone()), &payload.email).await {
+
+    // Los intentos se cuentan por correo y por IP; mientras haya un bloqueo ni se verifica el hash
+    let throttle_keys = throttle_keys(&state, &request_headers, &payload.email);
+    if is_locked(&state, &throttle_keys).await? {
+        return Err(AuthError::WrongCredentials);
+    }
+
+    let user = match UserRepository::find_by_email(State(state.clone()), &payload.email)
+        .await
+        .map_err(AuthError::DatabaseError)?
+    {
         Some(user) => user,
-        None => return Err(AuthError::WrongCredentials),
+        None => {
+            record_failed_login(&state, &throttle_keys).await?;
+            return Err(AuthError::WrongCredentials);
+        }
     };
 
     match helpers::hash_password::verify_password(user.clone().password, payload.password) {
         Ok(_) => {}
-        Err(_) => return Err(AuthError::WrongCredentials),
+        Err(_) => {
+            record_failed_login(&state, &throttle_keys).await?;
+            return Err(AuthError::WrongCredentials);
+        }
+    }
+    if user.disabled_at.is_some() {
+        return Err(AuthError::AccountDisabled);
     }
 
-    // add 4 minutes to current unix epoch time as expiry date/time
+    // Con verificación en dos pasos la sesión se emite recién al validar el código, y el
+    // contador del correo se reinicia recién ahí: si no, cada desafío nuevo daría más intentos
+    if user.totp_enabled_at.is_some() {
+        let challenge_token = tokens::generate_token();
+        let expires_at = chrono::Local::now().naive_local()
+            + chrono::Duration::seconds(TWO_FACTOR_CHALLENGE_TTL_SECS);
+        TwoFactorRepository::create_challenge(
+            &state.pool,
+            user.id,
+            &hash_token(&challenge_token),
+            expires_at,
+        )
+        .await
+        .map_err(AuthError::DatabaseError)?;
+
+        return Ok(Json(TwoFactorChallengeBody {
+            status: "two_factor_required".to_string(),
+            challenge_token,
+            expires_at,
+        })
+        .into_response());
+    }
+
+    // Un acceso correcto reinicia el contador del correo, pero no el de la IP
+    LoginAttemptRepository::reset(&state.pool, "email", &throttle_keys[0].1)
+        .await
+        .map_err(AuthError::DatabaseError)?;
+    let headers = issue_session(&state, &user).await?;
+
+    // Send the authorized token
+    Ok((headers, Json(AuthBody::new(user))).into_response())
+}
+
+// Segundo paso del inicio de sesión: código TOTP o uno de los códigos de recuperación
+pub async fn login_two_factor(
+    State(state): State<AppState>,
+    request_headers: HeaderMap,
+    Json(payload): Json<TwoFactorLoginPayload>,
+) -> Result<(HeaderMap, Json<AuthBody>), AuthError> {
+    let mut tx = state.pool.begin().await.map_err(AuthError::DatabaseError)?;
+    let challenge = TwoFactorRepository::find_challenge_for_update(
+        &mut tx,
+        &hash_token(&payload.challenge_token),
+    )
+    .await
+    .map_err(AuthError::DatabaseError)?
+    .filter(|challenge| {
+        challenge.used_at.is_none()
+            && challenge.failed_attempts < MAX_TWO_FACTOR_ATTEMPTS
+            && challenge.expires_at >= chrono::Local::now().naive_local()
+    })
+    .ok_or(AuthError::InvalidToken)?;
+
+    let user = UserRepository::find_by_id(&state.pool, challenge.user_id)
+        .await
+        .map_err(AuthError::DatabaseError)?
+        .ok_or(AuthError::InvalidToken)?;
+    if user.disabled_at.is_some() {
+        return Err(AuthError::AccountDisabled);
+    }
+
+    // Los fallos del segundo paso cuentan para el mismo bloqueo que los de la contraseña
+    let throttle_keys = throttle_keys(&state, &request_headers, &user.email);
+    if is_locked(&state, &throttle_keys).await? {
+        return Err(AuthError::InvalidTwoFactorCode);
+    }
+
+    let verified = match (&payload.code, &payload.recovery_code, &user.totp_secret) {
+        (Some(code), _, Some(secret)) => match totp::verify(secret, code) {
+            Some(step) => TwoFactorRepository::accept_step(&mut tx, user.id, step)
+                .await
+                .map_err(AuthError::DatabaseError)?,
+            None => false,
+        },
+        (None, Some(recovery_code), _) => TwoFactorRepository::use_recovery_code(
+            &mut tx,
+            user.id,
+            &hash_token(&normalize_recovery_code(recovery_code)),
+        )
+        .await
+        .map_err(AuthError::DatabaseError)?,
+        _ => false,
+    };
+
+    if !verified {
+        TwoFactorRepository::record_challenge_failure(&mut tx, challenge.id)
+            .await
+            .map_err(AuthError::DatabaseError)?;
+        tx.commit().await.map_err(AuthError::DatabaseError)?;
+        record_failed_login(&state, &throttle_keys).await?;
+        return Err(AuthError::InvalidTwoFactorCode);
+    }
+
+    TwoFactorRepository::mark_challenge_used(&mut tx, challenge.id)
+        .await
+        .map_err(AuthError::DatabaseError)?;
+    tx.commit().await.map_err(AuthError::DatabaseError)?;
+
+    LoginAttemptRepository::reset(&state.pool, "email", &throttle_keys[0].1)
+        .await
+        .map_err(AuthError::DatabaseError)?;
+
+    let headers = issue_session(&state, &user).await?;
+    Ok((headers, Json(AuthBody::new(user))))
+}
+
+// Los códigos de recuperación se comparan sin guiones ni mayúsculas
+pub fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+// Claves con las que se cuentan los intentos: el correo y, si se conoce, la IP del cliente
+fn throttle_keys(
+    state: &AppState,
+    request_headers: &HeaderMap,
+    email: &str,
+) -> Vec<(&'static str, String)> {
+    let mut throttle_keys = vec![("email", email.trim().to_lowercase())];
+    if let Some(ip) = client_ip(request_headers, state.trusted_proxy_hops) {
+        throttle_keys.push(("ip", ip));
+    }
+    throttle_keys
+}
+
+async fn is_locked(state: &AppState, throttle_keys: &[(&str, String)]) -> Result<bool, AuthError> {
+    let now = chrono::Local::now().naive_local();
+    for (kind, key) in throttle_keys {
+        let attempt = LoginAttemptRepository::find(&state.pool, kind, key)
+            .await
+            .map_err(AuthError::DatabaseError)?;
+        if attempt
+            .and_then(|attempt| attempt.locked_until)
+            .is_some_and(|locked_until| locked_until > now)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+// Registra el fallo para cada clave y aplica espera exponencial o bloqueo según la política.
+// Quien llama devuelve siempre el mismo error, para no revelar si la cuenta existe
+async fn record_failed_login(
+    state: &AppState,
+    throttle_keys: &[(&str, String)],
+) -> Result<(), AuthError> {
+    for (kind, key) in throttle_keys {
+        let policy = LoginThrottlePolicy::for_kind(kind);
+        let mut attempt =
+            LoginAttemptRepository::record_failure(&state.pool, kind, key, LOGIN_WINDOW_SECS)
+                .await
+                .map_err(AuthError::DatabaseError)?;
+
+        let Some(delay_secs) = policy.delay_secs(attempt.failed_count) else {
+            continue;
+        };
+        let locked_until =
+            chrono::Local::now().naive_local() + chrono::Duration::seconds(delay_secs);
+        LoginAttemptRepository::set_locked_until(&state.pool, attempt.id, locked_until)
+            .await
+            .map_err(AuthError::DatabaseError)?;
+
+        if attempt.failed_count >= policy.lockout_after {
+            attempt.locked_until = Some(locked_until);
+            LoginAttemptRepository::record_lockout(&state.pool, &attempt)
+                .await
+                .map_err(AuthError::DatabaseError)?;
+        }
+    }
+    Ok(())
+}
+
+struct LoginThrottlePolicy {
+    backoff_after: i32,
+    lockout_after: i32,
+}
+
+impl LoginThrottlePolicy {
+    // Una IP puede ser compartida por varios usuarios, así que tolera más fallos
+    fn for_kind(kind: &str) -> Self {
+        match kind {
+            "ip" => Self {
+                backoff_after: 10,
+                lockout_after: 20,
+            },
+            _ => Self {
+                backoff_after: 3,
+                lockout_after: 5,
+            },
+        }
+    }
+
+    // Segundos de espera antes del próximo intento, o None si todavía no corresponde
+    fn delay_secs(&self, failed_count: i32) -> Option<i64> {
+        if failed_count >= self.lockout_after {
+            Some(LOCKOUT_SECS)
+        } else if failed_count >= self.backoff_after {
+            let exponent = (failed_count - self.backoff_after) as u32;
+            Some(2_i64.pow(exponent).min(MAX_BACKOFF_SECS))
+        } else {
+            None
+        }
+    }
+}
+
+// Canjea el token de refresco por uno nuevo y un nuevo JWT de acceso
+pub async fn refresh(
+    State(state): State<AppState>,
+    request_headers: HeaderMap,
+) -> Result<(HeaderMap, Json<AuthBody>), AuthError> {
+    let token = cookies::find_cookie(&request_headers, REFRESH_TOKEN_COOKIE)
+        .ok_or(AuthError::MissingToken)?;
+
+    let mut tx = state.pool.begin().await.map_err(AuthError::DatabaseError)?;
+    let stored = RefreshTokenRepository::find_by_hash_for_update(&mut tx, &hash_token(&token))
+        .await
+        .map_err(AuthError::DatabaseError)?
+        .ok_or(AuthError::InvalidToken)?;
+
+    // Un token ya rotado que vuelve a usarse indica robo: se cierran todas las sesiones
+    if stored.revoked_at.is_some() {
+        RefreshTokenRepository::revoke_all_for_user(&mut tx, stored.user_id)
+            .await
+            .map_err(AuthError::DatabaseError)?;
+        tx.commit().await.map_err(AuthError::DatabaseError)?;
+        return Err(AuthError::InvalidToken);
+    }
+    if stored.expires_at < chrono::Local::now().naive_local() {
+        return Err(AuthError::InvalidToken);
+    }
+
+    let user = UserRepository::find_by_id(&state.pool, stored.user_id)
+        .await
+        .map_err(AuthError::DatabaseError)?
+        .ok_or(AuthError::InvalidToken)?;
+    if user.disabled_at.is_some() {
+        return Err(AuthError::AccountDisabled);
+    }
+
+    let (refresh_token, refresh_id) = create_refresh_token(&mut tx, user.id).await?;
+    RefreshTokenRepository::revoke(&mut tx, stored.id, Some(refresh_id))
+        .await
+        .map_err(AuthError::DatabaseError)?;
+    tx.commit().await.map_err(AuthError::DatabaseError)?;
+
+    let access_token = create_access_token(&state, &user)?;
+    Ok((
+        session_headers(access_token, refresh_token)?,
+        Json(AuthBody::new(user)),
+    ))
+}
+
+pub async fn logout(
+    State(state): State<AppState>,
+    request_headers: HeaderMap,
+) -> Result<(HeaderMap, Json<ApiResponse<String>>), AuthError> {
+    if let Some(token) = cookies::find_cookie(&request_headers, REFRESH_TOKEN_COOKIE) {
+        RefreshTokenRepository::revoke_by_hash(&state.pool, &hash_token(&token))
+            .await
+            .map_err(AuthError::DatabaseError)?;
+    }
+
+    let mut headers = HeaderMap::new();
+    set_cookie(&mut headers, ACCESS_TOKEN_COOKIE, String::new(), "/", 0)?;
+    set_cookie(
+        &mut headers,
+        REFRESH_TOKEN_COOKIE,
+        String::new(),
+        REFRESH_TOKEN_PATH,
+        0,
+    )?;
+    Ok((
+        headers,
+        Json(ApiResponse::new("Sesión cerrada".to_string())),
+    ))
+}
+
+// Crea el JWT de acceso y un token de refresco nuevo, y los devuelve como cookies
+async fn issue_session(state: &AppState, user: &User) -> Result<HeaderMap, AuthError> {
+    let access_token = create_access_token(state, user)?;
+
+    let mut tx = state.pool.begin().await.map_err(AuthError::DatabaseError)?;
+    let (refresh_token, _) = create_refresh_token(&mut tx, user.id).await?;
+    tx.commit().await.map_err(AuthError::DatabaseError)?;
+
+    session_headers(access_token, refresh_token)
+}
+
+fn create_access_token(state: &AppState, user: &User) -> Result<String, AuthError> {
     let exp = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
+        .map_err(|_| AuthError::TokenCreation)?
         .as_secs()
-        + 299;
+        + ACCESS_TOKEN_TTL_SECS as u64;
+
     // Create the authorization token
-    let encoding_jwt = &state.jwt_secret.encoding;
     let claims = Claims {
         sub: user.email.clone(),
         exp,
         role: user.rolename.clone(),
     };
-    let token =
-        encode(&Header::default(), &claims, encoding_jwt).map_err(|_| AuthError::TokenCreation)?;
+    encode(&Header::default(), &claims, &state.jwt_secret.encoding)
+        .map_err(|_| AuthError::TokenCreation)
+}
 
-    set_cookie("accessToken", token);
+async fn create_refresh_token(
+    conn: &mut sqlx::PgConnection,
+    user_id: Uuid,
+) -> Result<(String, Uuid), AuthError> {
+    let token = tokens::generate_token();
 
-    // Send the authorized token
-    Ok(Json(AuthBody::new(user)))
+    let expires_at =
+        chrono::Local::now().naive_local() + chrono::Duration::seconds(REFRESH_TOKEN_TTL_SECS);
+    let id = RefreshTokenRepository::create(conn, user_id, &hash_token(&token), expires_at)
+        .await
+        .map_err(AuthError::DatabaseError)?;
+    Ok((token, id))
 }
 
-pub fn set_cookie(type_of_cookie: &str, token: String) {
-    // Construye la cookie
-    let cookie = Cookie::build((type_of_cookie, token))
-        .http_only(true)
-        .secure(true) // Solo en HTTPS
-        .same_site(SameSite::Strict)
-        .path("/")
-        .build();
-
-    // Devuelve la cookie en la respuesta
+fn session_headers(access_token: String, refresh_token: String) -> Result<HeaderMap, AuthError> {
     let mut headers = HeaderMap::new();
-    headers.insert("Set-Cookie", cookie.to_string().parse().unwrap());
+    set_cookie(
+        &mut headers,
+        ACCESS_TOKEN_COOKIE,
+        access_token,
+        "/",
+        ACCESS_TOKEN_TTL_SECS,
+    )?;
+    set_cookie(
+        &mut headers,
+        REFRESH_TOKEN_COOKIE,
+        refresh_token,
+        REFRESH_TOKEN_PATH,
+        REFRESH_TOKEN_TTL_SECS,
+    )?;
+    Ok(headers)
+}
+
+pub fn set_cookie(
+    headers: &mut HeaderMap,
+    type_of_cookie: &str,
+    token: String,
+    path: &str,
+    max_age_secs: i64,
+) -> Result<(), AuthError> {
+    // Construye la cookie
+    let cookie = cookies::build_cookie(type_of_cookie, token, path, max_age_secs);
+    let value = cookie
+        .to_string()
+        .parse()
+        .map_err(|e| AuthError::Other(format!("Cookie inválida: {}", e)))?;
+
+    // Agrega la cookie a la respuesta
+    headers.append(SET_COOKIE, value);
+    Ok(())
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -133,3 +510,38 @@ impl<T> ApiResponse<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recovery_codes_ignore_dashes_spaces_and_case() {
+        assert_eq!(normalize_recovery_code("AB12-cd34"), "ab12cd34");
+        assert_eq!(normalize_recovery_code(" ab12 CD34 "), "ab12cd34");
+        assert_eq!(normalize_recovery_code("ab12cd34"), "ab12cd34");
+    }
+
+    #[test]
+    fn email_waits_exponentially_and_then_locks() {
+        let policy = LoginThrottlePolicy::for_kind("email");
+        assert_eq!(policy.delay_secs(0), None);
+        assert_eq!(policy.delay_secs(2), None);
+        assert_eq!(policy.delay_secs(3), Some(1));
+        assert_eq!(policy.delay_secs(4), Some(2));
+        assert_eq!(policy.delay_secs(5), Some(LOCKOUT_SECS));
+        assert_eq!(policy.delay_secs(50), Some(LOCKOUT_SECS));
+    }
+
+    #[test]
+    fn ip_tolerates_more_failures_and_caps_the_backoff() {
+        let policy = LoginThrottlePolicy::for_kind("ip");
+        assert_eq!(policy.delay_secs(9), None);
+        assert_eq!(policy.delay_secs(10), Some(1));
+        assert_eq!(policy.delay_secs(13), Some(8));
+        assert_eq!(policy.delay_secs(15), Some(32));
+        assert_eq!(policy.delay_secs(16), Some(MAX_BACKOFF_SECS));
+        assert_eq!(policy.delay_secs(19), Some(MAX_BACKOFF_SECS));
+        assert_eq!(policy.delay_secs(20), Some(LOCKOUT_SECS));
+    }
+}
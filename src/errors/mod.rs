@@ -4,52 +4,295 @@ use axum::{
     response::IntoResponse,
     Json,
 };
-use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+// Error único de la aplicación. Cada variante tiene un código estable que el cliente puede
+// interpretar; el detalle interno solo se registra en el servidor
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(&'static str),
+    BadRequest(String),
+    Validation(Vec<FieldError>),
+    Conflict(String),
+    AlreadyExists(String),
+    // Duplicado de un registro existente; se devuelve su id para que el cliente pueda ir a él
+    Duplicate { message: String, existing_id: Uuid },
+    InUse(String),
+    InvalidReference(String),
+    // El servidor está ocupado con otras tareas pesadas; se puede reintentar más tarde
+    Unavailable(String),
+    // Importación masiva rechazada; lleva el reporte fila por fila
+    ImportRejected(Value),
+    Auth(AuthError),
+    Internal(String),
+}
+
+// Error de validación de un campo puntual del cuerpo de la solicitud
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+}
+
+impl AppError {
+    // Devuelve Ok si no hubo errores de validación
+    pub fn validate(errors: Vec<FieldError>) -> Result<(), AppError> {
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(errors))
+        }
+    }
+
+    // Reemplazan el mensaje genérico de una violación de restricción por uno propio del caso
+    pub fn on_unique_violation(self, message: &str) -> Self {
+        match self {
+            AppError::AlreadyExists(_) => AppError::AlreadyExists(message.to_string()),
+            e => e,
+        }
+    }
+
+    pub fn on_invalid_reference(self, message: &str) -> Self {
+        match self {
+            AppError::InvalidReference(_) => AppError::InvalidReference(message.to_string()),
+            e => e,
+        }
+    }
+
+    // Para borrados: la única violación de clave foránea posible es que otra fila la referencie
+    pub fn on_in_use(self, message: &str) -> Self {
+        match self {
+            AppError::InvalidReference(_) | AppError::InUse(_) => {
+                AppError::InUse(message.to_string())
+            }
+            e => e,
+        }
+    }
+
+    fn parts(
+        self,
+    ) -> (
+        StatusCode,
+        &'static str,
+        String,
+        Option<(&'static str, Value)>,
+    ) {
+        match self {
+            AppError::NotFound(message) => (
+                StatusCode::NOT_FOUND,
+                "not_found",
+                message.to_string(),
+                None,
+            ),
+            AppError::BadRequest(message) => {
+                (StatusCode::BAD_REQUEST, "bad_request", message, None)
+            }
+            AppError::Validation(errors) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_failed",
+                "Los datos enviados no son válidos".to_string(),
+                Some(("details", json!(errors))),
+            ),
+            AppError::Conflict(message) => (StatusCode::CONFLICT, "conflict", message, None),
+            AppError::Unavailable(message) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "unavailable",
+                message,
+                None,
+            ),
+            AppError::AlreadyExists(message) => {
+                (StatusCode::CONFLICT, "already_exists", message, None)
+            }
+            AppError::Duplicate {
+                message,
+                existing_id,
+            } => (
+                StatusCode::CONFLICT,
+                "already_exists",
+                message,
+                Some(("existing_id", json!(existing_id))),
+            ),
+            AppError::InUse(message) => (StatusCode::CONFLICT, "in_use", message, None),
+            AppError::InvalidReference(message) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid_reference",
+                message,
+                None,
+            ),
+            AppError::ImportRejected(report) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "import_rejected",
+                "El archivo tiene errores; no se importó ningún registro".to_string(),
+                Some(("report", report)),
+            ),
+            AppError::Auth(e) => e.parts(),
+            AppError::Internal(detail) => {
+                tracing::error!("Error interno: {}", detail);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "internal_error",
+                    "Error interno del servidor".to_string(),
+                    None,
+                )
+            }
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response<Body> {
+        let (status, code, message, extra) = self.parts();
+
+        let mut body = json!({
+            "status": "error",
+            "code": code,
+            "error": message,
+        });
+        if let (Some((key, value)), Some(fields)) = (extra, body.as_object_mut()) {
+            fields.insert(key.to_string(), value);
+        }
+        (status, Json(body)).into_response()
+    }
+}
+
+// Las violaciones de restricciones se traducen a errores del cliente; el resto es interno
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        if let sqlx::Error::RowNotFound = e {
+            return AppError::NotFound("Recurso no encontrado");
+        }
+        if let Some(db_error) = e.as_database_error() {
+            if db_error.is_unique_violation() {
+                return AppError::AlreadyExists("Ya existe un registro con esos datos".to_string());
+            }
+            // Por defecto se asume un insert o update que referencia una fila que no existe; los
+            // borrados lo convierten en InUse con on_in_use, porque Postgres no distingue los dos
+            // casos más que en el texto del detalle, que depende del idioma del servidor
+            if db_error.is_foreign_key_violation() {
+                return AppError::InvalidReference(
+                    "Uno de los registros referenciados no existe".to_string(),
+                );
+            }
+            if db_error.is_check_violation() {
+                return AppError::BadRequest(
+                    "Los datos no cumplen las restricciones del sistema".to_string(),
+                );
+            }
+        }
+        AppError::Internal(e.to_string())
+    }
+}
+
+impl From<AuthError> for AppError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::DatabaseError(e) => AppError::from(e),
+            e => AppError::Auth(e),
+        }
+    }
+}
 
 // implement IntoResponse for AuthError so we can use it as an Axum response type
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response<Body> {
-        let (status, error_message) = match self {
+        AppError::from(self).into_response()
+    }
+}
+
+impl AuthError {
+    fn parts(
+        self,
+    ) -> (
+        StatusCode,
+        &'static str,
+        String,
+        Option<(&'static str, Value)>,
+    ) {
+        let (status, code, message) = match self {
             AuthError::WrongCredentials => (
                 StatusCode::UNAUTHORIZED,
+                "invalid_credentials",
                 "Credenciales incorrectas".to_string(),
             ),
             AuthError::MissingCredentials => (
                 StatusCode::BAD_REQUEST,
+                "missing_credentials",
                 "Faltan las credenciales".to_string(),
             ),
-            AuthError::TokenCreation => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Error al crear token".to_string(),
+            AuthError::InvalidToken => (
+                StatusCode::UNAUTHORIZED,
+                "invalid_token",
+                "Token inválido".to_string(),
             ),
-            AuthError::InvalidToken => (StatusCode::BAD_REQUEST, "Token inválido".to_string()),
+            AuthError::MissingToken => (
+                StatusCode::UNAUTHORIZED,
+                "missing_token",
+                "Falta el token".to_string(),
+            ),
+            AuthError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "forbidden",
+                "Acceso no autorizado".to_string(),
+            ),
+            AuthError::AccountDisabled => (
+                StatusCode::FORBIDDEN,
+                "account_disabled",
+                "La cuenta está deshabilitada".to_string(),
+            ),
+            AuthError::InvalidTwoFactorCode => (
+                StatusCode::UNAUTHORIZED,
+                "invalid_two_factor_code",
+                "Código de verificación incorrecto".to_string(),
+            ),
+            AuthError::TwoFactorEnrollmentRequired => (
+                StatusCode::FORBIDDEN,
+                "two_factor_enrollment_required",
+                "Tu rol exige verificación en dos pasos; activala para continuar".to_string(),
+            ),
+            AuthError::TwoFactorAlreadyEnabled => (
+                StatusCode::CONFLICT,
+                "two_factor_already_enabled",
+                "La verificación en dos pasos ya está activada".to_string(),
+            ),
+            AuthError::TwoFactorNotEnabled => (
+                StatusCode::BAD_REQUEST,
+                "two_factor_not_enabled",
+                "La verificación en dos pasos no está activada".to_string(),
+            ),
+            AuthError::TwoFactorRequiredByRole => (
+                StatusCode::BAD_REQUEST,
+                "two_factor_required_by_role",
+                "Tu rol exige verificación en dos pasos; no se puede desactivar".to_string(),
+            ),
+            AuthError::RegistrationDisabled => (
+                StatusCode::FORBIDDEN,
+                "registration_disabled",
+                "El registro público está deshabilitado".to_string(),
+            ),
+            AuthError::DatabaseError(e) => return AppError::from(e).parts(),
+            AuthError::TokenCreation => {
+                return AppError::Internal("Error al crear token".to_string()).parts()
+            }
             AuthError::HashingError(e) => {
-                let error = format!("Problema al hashear: {}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, error)
+                return AppError::Internal(format!("Problema al hashear: {}", e)).parts()
             }
-            AuthError::DatabaseError(e) => {
-                let error = format!("Error en la base de datos: {}", e.to_string());
-                (StatusCode::INTERNAL_SERVER_ERROR, error)
+            AuthError::MissingAppState => {
+                return AppError::Internal("Falta el AppState".to_string()).parts()
             }
-            AuthError::MissingToken => (StatusCode::BAD_REQUEST, "Falta el token".to_string()),
-            AuthError::Other(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
-            AuthError::MissingAppState => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Falta el AppState".to_string(),
-            ),
-            AuthError::Forbidden => (StatusCode::FORBIDDEN, "Acceso no autorizado".to_string()),
-            AuthError::UserNotFound(email) => (
-                StatusCode::NOT_FOUND,
-                format!("Usuario \"{}\" no encontrado", email),
-            ),
+            AuthError::Other(e) => return AppError::Internal(e).parts(),
         };
-
-        let body = Json(json!({
-            "status": "error",
-            "error": error_message,
-        }));
-        (status, body).into_response()
+        (status, code, message, None)
     }
 }
 
@@ -66,5 +309,118 @@ pub enum AuthError {
     MissingToken,
     Other(String), // Otros errores
     Forbidden,
-    UserNotFound(String),
+    AccountDisabled,
+    InvalidTwoFactorCode,
+    TwoFactorEnrollmentRequired,
+    TwoFactorAlreadyEnabled,
+    TwoFactorNotEnabled,
+    TwoFactorRequiredByRole,
+    RegistrationDisabled,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, error::Error, fmt};
+
+    use sqlx::error::{DatabaseError, ErrorKind};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FakeDbError(ErrorKind);
+
+    impl fmt::Display for FakeDbError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+
+    impl Error for FakeDbError {}
+
+    impl DatabaseError for FakeDbError {
+        fn message(&self) -> &str {
+            "error de prueba"
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            None
+        }
+
+        fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            match &self.0 {
+                ErrorKind::UniqueViolation => ErrorKind::UniqueViolation,
+                ErrorKind::ForeignKeyViolation => ErrorKind::ForeignKeyViolation,
+                ErrorKind::CheckViolation => ErrorKind::CheckViolation,
+                _ => ErrorKind::Other,
+            }
+        }
+    }
+
+    fn db_error(kind: ErrorKind) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(FakeDbError(kind)))
+    }
+
+    #[test]
+    fn row_not_found_is_not_found() {
+        assert!(matches!(
+            AppError::from(sqlx::Error::RowNotFound),
+            AppError::NotFound(_)
+        ));
+    }
+
+    #[test]
+    fn unique_violation_is_already_exists() {
+        let error =
+            AppError::from(db_error(ErrorKind::UniqueViolation)).on_unique_violation("Ya existe");
+        assert!(matches!(error, AppError::AlreadyExists(message) if message == "Ya existe"));
+    }
+
+    #[test]
+    fn foreign_key_violation_is_invalid_reference() {
+        let error = AppError::from(db_error(ErrorKind::ForeignKeyViolation))
+            .on_invalid_reference("No existe");
+        assert!(matches!(error, AppError::InvalidReference(message) if message == "No existe"));
+    }
+
+    #[test]
+    fn foreign_key_violation_on_delete_is_in_use() {
+        let error = AppError::from(db_error(ErrorKind::ForeignKeyViolation)).on_in_use("En uso");
+        assert!(matches!(error, AppError::InUse(message) if message == "En uso"));
+        assert_eq!(
+            AppError::InUse(String::new()).parts().0,
+            StatusCode::CONFLICT
+        );
+    }
+
+    #[test]
+    fn check_violation_is_bad_request() {
+        assert!(matches!(
+            AppError::from(db_error(ErrorKind::CheckViolation)),
+            AppError::BadRequest(_)
+        ));
+    }
+
+    #[test]
+    fn other_errors_are_internal() {
+        assert!(matches!(
+            AppError::from(db_error(ErrorKind::Other)),
+            AppError::Internal(_)
+        ));
+        assert!(matches!(
+            AppError::from(sqlx::Error::PoolTimedOut),
+            AppError::Internal(_)
+        ));
+    }
 }
@@ -0,0 +1,67 @@
+use axum::http::HeaderMap;
+
+// IP del cliente según los proxies de confianza. Cada proxy agrega al final de X-Forwarded-For
+// la dirección de quien se le conectó, así que con `trusted_hops` proxies delante del servidor
+// la IP real es la que está en esa posición contando desde la derecha. Lo que queda a la
+// izquierda lo manda el cliente y no se puede creer. Sin proxies de confianza no hay IP
+pub fn client_ip(headers: &HeaderMap, trusted_hops: usize) -> Option<String> {
+    if trusted_hops == 0 {
+        return None;
+    }
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    hops.len()
+        .checked_sub(trusted_hops)
+        .and_then(|index| hops.get(index))
+        .filter(|ip| !ip.is_empty())
+        .map(|ip| ip.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("x-forwarded-for", value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn without_trusted_proxies_there_is_no_ip() {
+        let headers = forwarded(&["203.0.113.7"]);
+        assert_eq!(client_ip(&headers, 0), None);
+    }
+
+    #[test]
+    fn takes_the_entry_added_by_the_outermost_trusted_proxy() {
+        let headers = forwarded(&["198.51.100.1, 203.0.113.7 ,10.0.0.2"]);
+        assert_eq!(client_ip(&headers, 1).as_deref(), Some("10.0.0.2"));
+        assert_eq!(client_ip(&headers, 2).as_deref(), Some("203.0.113.7"));
+        assert_eq!(client_ip(&headers, 3).as_deref(), Some("198.51.100.1"));
+    }
+
+    #[test]
+    fn joins_repeated_headers_in_order() {
+        let headers = forwarded(&["198.51.100.1", "203.0.113.7, 10.0.0.2"]);
+        assert_eq!(client_ip(&headers, 3).as_deref(), Some("198.51.100.1"));
+    }
+
+    #[test]
+    fn more_trusted_proxies_than_entries_gives_no_ip() {
+        assert_eq!(client_ip(&forwarded(&["203.0.113.7"]), 2), None);
+        assert_eq!(client_ip(&HeaderMap::new(), 1), None);
+    }
+
+    #[test]
+    fn ignores_empty_entries() {
+        assert_eq!(client_ip(&forwarded(&["203.0.113.7, "]), 1), None);
+    }
+}
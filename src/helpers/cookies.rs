@@ -0,0 +1,30 @@
+use axum::http::HeaderMap;
+use cookie::{Cookie, SameSite};
+
+pub const ACCESS_TOKEN_COOKIE: &str = "accessToken";
+pub const REFRESH_TOKEN_COOKIE: &str = "refreshToken";
+
+// El token de refresco solo viaja a las rutas de usuario
+pub const REFRESH_TOKEN_PATH: &str = "/api/v1/user";
+
+pub fn build_cookie(name: &str, value: String, path: &str, max_age_secs: i64) -> Cookie<'static> {
+    Cookie::build((name.to_string(), value))
+        .http_only(true)
+        .secure(true) // Solo en HTTPS
+        .same_site(SameSite::Strict)
+        .path(path.to_string())
+        .max_age(cookie::time::Duration::seconds(max_age_secs))
+        .build()
+}
+
+// Busca una cookie por nombre entre todas las que envía el cliente
+pub fn find_cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(Cookie::split_parse)
+        .filter_map(Result::ok)
+        .find(|cookie| cookie.name() == name)
+        .map(|cookie| cookie.value().to_string())
+}
@@ -0,0 +1,14 @@
+use chrono::{Datelike, NaiveDate};
+
+// Edad en años cumplidos a la fecha indicada
+pub fn age_at(birth_date: NaiveDate, date: NaiveDate) -> i32 {
+    let mut age = date.year() - birth_date.year();
+    if (date.month(), date.day()) < (birth_date.month(), birth_date.day()) {
+        age -= 1;
+    }
+    age
+}
+
+pub fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
+}
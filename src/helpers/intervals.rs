@@ -0,0 +1,83 @@
+use chrono::NaiveTime;
+
+// Huecos libres dentro de [opens, closes] dado un conjunto de intervalos ocupados
+pub fn free_gaps(
+    opens: NaiveTime,
+    closes: NaiveTime,
+    busy: &[(NaiveTime, NaiveTime)],
+) -> Vec<(NaiveTime, NaiveTime)> {
+    let mut busy = busy.to_vec();
+    busy.sort();
+
+    let mut gaps = vec![];
+    let mut cursor = opens;
+    for (start, end) in busy {
+        if start > cursor {
+            gaps.push((cursor, start.min(closes)));
+        }
+        cursor = cursor.max(end);
+        if cursor >= closes {
+            break;
+        }
+    }
+    if cursor < closes {
+        gaps.push((cursor, closes));
+    }
+    gaps.retain(|(start, end)| start < end);
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn nothing_busy_leaves_the_whole_day_free() {
+        assert_eq!(free_gaps(t(8, 0), t(22, 0), &[]), vec![(t(8, 0), t(22, 0))]);
+    }
+
+    #[test]
+    fn gaps_between_unsorted_busy_intervals() {
+        let busy = [(t(14, 0), t(15, 0)), (t(9, 0), t(10, 30))];
+        assert_eq!(
+            free_gaps(t(8, 0), t(22, 0), &busy),
+            vec![
+                (t(8, 0), t(9, 0)),
+                (t(10, 30), t(14, 0)),
+                (t(15, 0), t(22, 0))
+            ]
+        );
+    }
+
+    #[test]
+    fn overlapping_and_adjacent_intervals_merge() {
+        let busy = [
+            (t(9, 0), t(11, 0)),
+            (t(10, 0), t(12, 0)),
+            (t(12, 0), t(13, 0)),
+        ];
+        assert_eq!(
+            free_gaps(t(9, 0), t(18, 0), &busy),
+            vec![(t(13, 0), t(18, 0))]
+        );
+    }
+
+    #[test]
+    fn intervals_outside_opening_hours_are_clipped() {
+        let busy = [(t(6, 0), t(9, 0)), (t(20, 0), t(23, 0))];
+        assert_eq!(
+            free_gaps(t(8, 0), t(22, 0), &busy),
+            vec![(t(9, 0), t(20, 0))]
+        );
+    }
+
+    #[test]
+    fn fully_booked_day_has_no_gaps() {
+        let busy = [(t(7, 0), t(23, 0))];
+        assert!(free_gaps(t(8, 0), t(22, 0), &busy).is_empty());
+    }
+}
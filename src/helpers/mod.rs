@@ -1,2 +1,13 @@
 pub mod claims;
+pub mod client_ip;
+pub mod cookies;
+pub mod dates;
+pub mod extract;
 pub mod hash_password;
+pub mod intervals;
+pub mod nullable;
+pub mod pagination;
+pub mod spreadsheet;
+pub mod tokens;
+pub mod totp;
+pub mod validation;
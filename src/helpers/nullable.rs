@@ -0,0 +1,11 @@
+use serde::{Deserialize, Deserializer};
+
+// Para actualizaciones parciales: distingue un campo ausente (None, no se toca) de uno enviado
+// como null (Some(None), se borra). Se usa con #[serde(default, deserialize_with = "...")]
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
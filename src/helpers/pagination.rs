@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 200;
+
+// Parámetros ?page=&per_page= de los listados paginados; page empieza en 1
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl PageQuery {
+    pub fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> i64 {
+        self.per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .clamp(1, MAX_PER_PAGE)
+    }
+
+    // Una página absurda da una página vacía en vez de desbordar
+    pub fn offset(&self) -> i64 {
+        (self.page() - 1).saturating_mul(self.per_page())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pagination {
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+    pub total_pages: i64,
+}
+
+impl Pagination {
+    pub fn new(query: &PageQuery, total: i64) -> Self {
+        let per_page = query.per_page();
+        Self {
+            page: query.page(),
+            per_page,
+            total,
+            total_pages: (total + per_page - 1) / per_page,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(page: Option<i64>, per_page: Option<i64>) -> PageQuery {
+        PageQuery { page, per_page }
+    }
+
+    #[test]
+    fn defaults_to_the_first_page() {
+        let query = query(None, None);
+        assert_eq!(query.page(), 1);
+        assert_eq!(query.per_page(), DEFAULT_PER_PAGE);
+        assert_eq!(query.offset(), 0);
+    }
+
+    #[test]
+    fn offset_skips_the_previous_pages() {
+        assert_eq!(query(Some(3), Some(20)).offset(), 40);
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped() {
+        assert_eq!(query(Some(0), Some(0)).offset(), 0);
+        assert_eq!(query(Some(-5), None).page(), 1);
+        assert_eq!(query(Some(2), Some(10_000)).offset(), MAX_PER_PAGE);
+    }
+
+    #[test]
+    fn huge_pages_saturate_instead_of_overflowing() {
+        assert_eq!(query(Some(i64::MAX), None).offset(), i64::MAX);
+        assert_eq!(query(Some(i64::MAX), Some(i64::MAX)).offset(), i64::MAX);
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    future::Future,
+    io::{self, Cursor, Seek},
+    time::Duration,
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use calamine::{open_workbook_from_rs, Data, DataType, Reader, Xlsx};
+use chrono::{NaiveDate, NaiveTime};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use futures_util::stream;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use serde::Deserialize;
+use tokio::{
+    io::AsyncReadExt,
+    sync::{mpsc, oneshot, Semaphore},
+};
+
+use crate::{errors::AppError, helpers::dates};
+
+// Las filas se mandan al cliente en bloques de este tamaño a medida que salen de la base
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// Mientras dura una exportación se ocupa una conexión del pool (el cursor de la consulta), y un
+// cliente lento la retiene. Se limitan las exportaciones simultáneas y su duración para que
+// las descargas no dejen sin conexiones al resto de la API
+const MAX_CONCURRENT_EXPORTS: usize = 2;
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+static EXPORT_SLOTS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_EXPORTS);
+
+// Fila leída de una planilla. `line` es el número de fila tal como lo ve el usuario (desde 1),
+// que no coincide con la posición en la lista: el CSV saltea las líneas en blanco y el XLSX
+// arranca en la primera celda con datos
+#[derive(Debug)]
+pub struct SheetRow {
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+// Lee la primera hoja de un CSV o XLSX como filas de texto; la primera fila es el encabezado.
+// El formato se deduce de la extensión del archivo
+pub fn read_rows(file_name: &str, bytes: &[u8]) -> Result<Vec<SheetRow>, AppError> {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "csv" => read_csv(bytes),
+        "xlsx" => read_xlsx(bytes),
+        _ => Err(AppError::BadRequest(
+            "El archivo debe ser .csv o .xlsx".to_string(),
+        )),
+    }
+}
+
+fn read_csv(bytes: &[u8]) -> Result<Vec<SheetRow>, AppError> {
+    // Las planillas exportadas desde Excel en español suelen venir separadas por punto y coma
+    let first_line = bytes.split(|b| *b == b'\n').next().unwrap_or_default();
+    let semicolons = first_line.iter().filter(|b| **b == b';').count();
+    let commas = first_line.iter().filter(|b| **b == b',').count();
+    let delimiter = if semicolons > commas { b';' } else { b',' };
+
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    // Excel en Windows guarda el CSV en Windows-1252 si no se elige UTF-8; se detecta por archivo
+    // para no mezclar codificaciones entre celdas
+    let encoding: &Encoding = if std::str::from_utf8(bytes).is_ok() {
+        UTF_8
+    } else {
+        WINDOWS_1252
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(bytes);
+
+    // La línea se cuenta a partir de la posición en bytes de cada registro, porque el contador de
+    // líneas del lector no suma las líneas en blanco que saltea
+    let mut rows = vec![];
+    let (mut line, mut counted) = (1, 0);
+    for record in reader.byte_records() {
+        let record =
+            record.map_err(|e| AppError::BadRequest(format!("El CSV no es válido: {}", e)))?;
+        let start = record
+            .position()
+            .map(|position| position.byte() as usize)
+            .unwrap_or(counted)
+            .clamp(counted, bytes.len());
+        let blank = bytes[start..]
+            .iter()
+            .take_while(|b| matches!(b, b'\r' | b'\n'))
+            .count();
+        let start = start + blank;
+        line += bytes[counted..start]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count();
+        counted = start;
+        rows.push(SheetRow {
+            line,
+            cells: record
+                .iter()
+                .map(|cell| {
+                    encoding
+                        .decode_without_bom_handling(cell)
+                        .0
+                        .trim()
+                        .to_string()
+                })
+                .collect(),
+        });
+    }
+    Ok(rows)
+}
+
+fn read_xlsx(bytes: &[u8]) -> Result<Vec<SheetRow>, AppError> {
+    let invalid = |e: calamine::XlsxError| {
+        AppError::BadRequest(format!("El archivo XLSX no es válido: {}", e))
+    };
+    let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes)).map_err(invalid)?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .ok_or(AppError::BadRequest(
+            "El archivo XLSX no tiene hojas".to_string(),
+        ))?
+        .map_err(invalid)?;
+
+    // start() es la primera celda con datos, contando desde 0
+    let first_line = range.start().map(|(row, _)| row as usize + 1).unwrap_or(1);
+    Ok(range
+        .rows()
+        .enumerate()
+        .map(|(index, row)| SheetRow {
+            line: first_line + index,
+            cells: row.iter().map(cell_to_string).collect(),
+        })
+        .collect())
+}
+
+// Las fechas se pasan a AAAA-MM-DD y los números enteros sin decimales (cédulas, teléfonos)
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::DateTime(_) | Data::DateTimeIso(_) => cell
+            .as_date()
+            .map(|date| date.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        Data::Float(number) if number.fract() == 0.0 => format!("{}", *number as i64),
+        Data::Empty | Data::Error(_) => String::new(),
+        _ => cell.to_string().trim().to_string(),
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+// Parámetro ?format=csv|xlsx de los endpoints de exportación
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+// Valor de una celda exportada; en XLSX números y fechas conservan su tipo
+pub enum Cell {
+    Text(String),
+    Number(Decimal),
+    Date(NaiveDate),
+    Empty,
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl From<Decimal> for Cell {
+    fn from(value: Decimal) -> Self {
+        Cell::Number(value)
+    }
+}
+
+impl From<i32> for Cell {
+    fn from(value: i32) -> Self {
+        Cell::Number(Decimal::from(value))
+    }
+}
+
+impl From<NaiveDate> for Cell {
+    fn from(value: NaiveDate) -> Self {
+        Cell::Date(value)
+    }
+}
+
+impl From<NaiveTime> for Cell {
+    fn from(value: NaiveTime) -> Self {
+        Cell::Text(value.format("%H:%M").to_string())
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(value: bool) -> Self {
+        Cell::Text(if value { "Sí" } else { "No" }.to_string())
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Cell::Empty)
+    }
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+    Database(sqlx::Error),
+    Csv(csv::Error),
+    Xlsx(XlsxError),
+    Io(io::Error),
+    // El cliente cortó la descarga
+    Closed,
+}
+
+impl From<sqlx::Error> for ExportError {
+    fn from(e: sqlx::Error) -> Self {
+        ExportError::Database(e)
+    }
+}
+
+impl From<csv::Error> for ExportError {
+    fn from(e: csv::Error) -> Self {
+        ExportError::Csv(e)
+    }
+}
+
+impl From<XlsxError> for ExportError {
+    fn from(e: XlsxError) -> Self {
+        ExportError::Xlsx(e)
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        ExportError::Io(e)
+    }
+}
+
+type Chunk = Result<Bytes, io::Error>;
+
+// Recibe las filas de la exportación. En CSV cada bloque se envía apenas se llena; en XLSX
+// la hoja se escribe en modo de memoria constante (las filas van a un archivo temporal)
+// y el archivo terminado se envía por partes
+pub struct RowWriter {
+    output: Output,
+    sender: mpsc::Sender<Chunk>,
+}
+
+enum Output {
+    Csv(Vec<u8>),
+    Xlsx(Box<XlsxOutput>),
+}
+
+struct XlsxOutput {
+    workbook: Workbook,
+    text_format: Format,
+    date_format: Format,
+    row: u32,
+}
+
+impl RowWriter {
+    fn new(
+        format: ExportFormat,
+        headers: &[&str],
+        sender: mpsc::Sender<Chunk>,
+    ) -> Result<Self, ExportError> {
+        let output = match format {
+            ExportFormat::Csv => {
+                // BOM para que Excel abra el archivo como UTF-8 y respete los tildes
+                let mut buffer = b"\xEF\xBB\xBF".to_vec();
+                write_csv_record(&mut buffer, headers.iter().copied())?;
+                Output::Csv(buffer)
+            }
+            ExportFormat::Xlsx => {
+                let mut workbook = Workbook::new();
+                let header_format = Format::new().set_bold();
+                let worksheet = workbook.add_worksheet_with_constant_memory();
+                for (col, title) in headers.iter().enumerate() {
+                    worksheet.write_string_with_format(0, col as u16, *title, &header_format)?;
+                }
+                worksheet.set_freeze_panes(1, 0)?;
+                Output::Xlsx(Box::new(XlsxOutput {
+                    workbook,
+                    text_format: Format::new().set_num_format("@"),
+                    date_format: Format::new().set_num_format("dd/mm/yyyy"),
+                    row: 1,
+                }))
+            }
+        };
+        Ok(Self { output, sender })
+    }
+
+    pub async fn write(&mut self, cells: Vec<Cell>) -> Result<(), ExportError> {
+        match &mut self.output {
+            Output::Csv(buffer) => {
+                write_csv_record(buffer, cells.iter().map(cell_to_text))?;
+                if buffer.len() >= CHUNK_SIZE {
+                    let chunk = std::mem::take(buffer);
+                    send(&self.sender, chunk).await?;
+                }
+            }
+            Output::Xlsx(xlsx) => {
+                let XlsxOutput {
+                    workbook,
+                    text_format,
+                    date_format,
+                    row,
+                } = xlsx.as_mut();
+                let worksheet = workbook.worksheet_from_index(0)?;
+                for (col, cell) in cells.iter().enumerate() {
+                    let col = col as u16;
+                    match cell {
+                        // write_string nunca genera fórmulas; el formato de texto evita que Excel
+                        // reinterprete la celda si se edita
+                        Cell::Text(text) => {
+                            worksheet.write_string_with_format(*row, col, text, text_format)?;
+                        }
+                        Cell::Number(number) => {
+                            worksheet.write_number(
+                                *row,
+                                col,
+                                number.to_f64().unwrap_or_default(),
+                            )?;
+                        }
+                        Cell::Date(date) => {
+                            worksheet.write_date_with_format(*row, col, date, date_format)?;
+                        }
+                        Cell::Empty => {}
+                    }
+                }
+                *row += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn finish(self) -> Result<(), ExportError> {
+        match self.output {
+            Output::Csv(buffer) => send(&self.sender, buffer).await,
+            Output::Xlsx(mut xlsx) => {
+                let file = tokio::task::spawn_blocking(move || -> Result<_, ExportError> {
+                    let mut file = tempfile::tempfile()?;
+                    xlsx.workbook.save_to_writer(&mut file)?;
+                    file.rewind()?;
+                    Ok(file)
+                })
+                .await
+                .map_err(|e| ExportError::Io(io::Error::other(e)))??;
+
+                let mut file = tokio::fs::File::from_std(file);
+                loop {
+                    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+                    if file.read_buf(&mut chunk).await? == 0 {
+                        return Ok(());
+                    }
+                    send(&self.sender, chunk).await?;
+                }
+            }
+        }
+    }
+}
+
+async fn send(sender: &mpsc::Sender<Chunk>, chunk: Vec<u8>) -> Result<(), ExportError> {
+    sender
+        .send(Ok(Bytes::from(chunk)))
+        .await
+        .map_err(|_| ExportError::Closed)
+}
+
+fn write_csv_record<I, T>(buffer: &mut Vec<u8>, record: I) -> Result<(), csv::Error>
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    let mut writer = csv::WriterBuilder::new().from_writer(buffer);
+    writer.write_record(record)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Un texto que empieza con = + - @ (o tabulador/retorno) Excel lo toma como fórmula al abrir el
+// CSV; con el apóstrofo adelante se muestra como texto. Nombres y observaciones los carga
+// cualquiera, así que no se puede confiar en su contenido
+fn cell_to_text(cell: &Cell) -> String {
+    match cell {
+        Cell::Text(text) if text.starts_with(['=', '+', '-', '@', '\t', '\r']) => {
+            format!("'{}", text)
+        }
+        Cell::Text(text) => text.clone(),
+        Cell::Number(number) => number.to_string(),
+        Cell::Date(date) => date.format("%d/%m/%Y").to_string(),
+        Cell::Empty => String::new(),
+    }
+}
+
+// Arma la respuesta de descarga y genera el archivo en segundo plano: `write_rows` recibe el
+// RowWriter, escribe las filas a medida que las lee de la base y lo devuelve al terminar.
+// Si algo falla a mitad de camino (o se pasa de EXPORT_TIMEOUT) se corta la descarga, porque el
+// encabezado 200 ya se envió
+pub fn export<F, Fut>(
+    format: ExportFormat,
+    file_name: &str,
+    headers: &'static [&'static str],
+    write_rows: F,
+) -> Result<Response, AppError>
+where
+    F: FnOnce(RowWriter) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<RowWriter, ExportError>> + Send,
+{
+    let permit = EXPORT_SLOTS.try_acquire().map_err(|_| {
+        AppError::Unavailable(
+            "Hay otras exportaciones en curso, intente de nuevo en unos minutos".to_string(),
+        )
+    })?;
+
+    let (sender, receiver) = mpsc::channel::<Chunk>(4);
+    // Avisa al cuerpo de la respuesta que la exportación terminó bien. Va aparte del canal de
+    // datos porque ese puede estar lleno justo cuando falla (un cliente lento que hace vencer el
+    // tiempo) y el error se perdería, dejando un archivo cortado que parece completo
+    let (done_sender, done_receiver) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let _permit = permit;
+        let result = tokio::time::timeout(EXPORT_TIMEOUT, async {
+            let writer = RowWriter::new(format, headers, sender)?;
+            write_rows(writer).await?.finish().await
+        })
+        .await;
+        match result {
+            Ok(Ok(())) | Ok(Err(ExportError::Closed)) => {
+                let _ = done_sender.send(());
+            }
+            Ok(Err(e)) => tracing::error!("Error al generar la exportación: {:?}", e),
+            Err(_) => tracing::warn!("Exportación cortada por superar el tiempo máximo"),
+        }
+    });
+
+    let (extension, content_type) = match format {
+        ExportFormat::Csv => ("csv", "text/csv; charset=utf-8"),
+        ExportFormat::Xlsx => (
+            "xlsx",
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        ),
+    };
+    let disposition = format!(
+        "attachment; filename=\"{}-{}.{}\"",
+        file_name,
+        dates::today(),
+        extension
+    );
+    // Cuando se acaban los datos, la descarga solo termina bien si llegó el aviso; si no, se corta
+    // con error para que el cliente no dé el archivo por completo
+    let body = Body::from_stream(stream::unfold(
+        (receiver, Some(done_receiver)),
+        |(mut receiver, done_receiver)| async move {
+            if let Some(chunk) = receiver.recv().await {
+                return Some((chunk, (receiver, done_receiver)));
+            }
+            match done_receiver?.await {
+                Ok(()) => None,
+                Err(_) => Some((
+                    Err(io::Error::other("Exportación interrumpida")),
+                    (receiver, None),
+                )),
+            }
+        },
+    ));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_keep_their_line_numbers() {
+        let rows = read_rows(
+            "socios.csv",
+            b"nombre;ci\n\nAna;12345672\n\n\nLuis;1234561\n",
+        )
+        .unwrap();
+        let lines: Vec<_> = rows.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [1, 3, 6]);
+        assert_eq!(rows[1].cells, ["Ana", "12345672"]);
+    }
+
+    #[test]
+    fn xlsx_rows_start_at_the_first_used_row() {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        sheet.write_string(2, 0, "nombre").unwrap();
+        sheet.write_string(3, 0, "Ana").unwrap();
+        let bytes = workbook.save_to_buffer().unwrap();
+
+        let rows = read_rows("socios.xlsx", &bytes).unwrap();
+        let lines: Vec<_> = rows.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [3, 4]);
+    }
+
+    #[test]
+    fn csv_falls_back_to_windows_1252() {
+        let rows = read_rows("socios.csv", b"nombre,apellido\nJos\xe9,Pe\xf1a\n").unwrap();
+        assert_eq!(rows[1].cells, ["José", "Peña"]);
+    }
+}
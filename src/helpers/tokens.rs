@@ -0,0 +1,14 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+// Token opaco de 256 bits en hexadecimal
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// En la base solo se guarda el hash de los tokens
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
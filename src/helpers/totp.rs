@@ -0,0 +1,112 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const ISSUER: &str = "MVD";
+
+fn build(secret_bytes: Vec<u8>, account_name: &str) -> Option<TOTP> {
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret_bytes,
+        Some(ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .ok()
+}
+
+// Secreto nuevo en base32, tal como lo guardan las apps autenticadoras
+pub fn generate_secret() -> String {
+    let bytes = Secret::generate_secret()
+        .to_bytes()
+        .expect("un secreto generado siempre es válido");
+    build(bytes, "")
+        .expect("un secreto generado siempre es válido")
+        .get_secret_base32()
+}
+
+// URI otpauth:// para mostrar como código QR
+pub fn otpauth_uri(secret: &str, account_name: &str) -> Option<String> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    Some(build(bytes, account_name)?.get_url())
+}
+
+// Acepta el código actual o el del intervalo anterior/siguiente y devuelve el intervalo al que
+// corresponde. Quien llama debe registrarlo y rechazar intervalos ya usados, porque un mismo
+// código sirve durante los tres intervalos y si no se podría reutilizar
+pub fn verify(secret: &str, code: &str) -> Option<i64> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    verify_at(secret, code, now)
+}
+
+fn verify_at(secret: &str, code: &str, now: u64) -> Option<i64> {
+    let bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    let totp = build(bytes, "")?;
+    let current = now / totp.step;
+    let code = code.trim().as_bytes();
+
+    [current.saturating_sub(1), current, current + 1]
+        .into_iter()
+        .find(|step| {
+            let expected = totp.generate(step * totp.step);
+            // Comparación sin cortar al primer byte distinto
+            expected.len() == code.len()
+                && expected
+                    .bytes()
+                    .zip(code)
+                    .fold(0, |diff, (a, b)| diff | (a ^ b))
+                    == 0
+        })
+        .map(|step| step as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn code_at(secret: &str, time: u64) -> String {
+        let bytes = Secret::Encoded(secret.to_string()).to_bytes().unwrap();
+        build(bytes, "").unwrap().generate(time)
+    }
+
+    #[test]
+    fn accepts_the_current_step_and_its_neighbours() {
+        let secret = generate_secret();
+        let current = NOW / 30;
+        for step in [current - 1, current, current + 1] {
+            let code = code_at(&secret, step * 30);
+            assert_eq!(verify_at(&secret, &code, NOW), Some(step as i64));
+        }
+    }
+
+    #[test]
+    fn rejects_steps_further_away() {
+        let secret = generate_secret();
+        let current = NOW / 30;
+        for step in [current - 2, current + 2] {
+            let code = code_at(&secret, step * 30);
+            assert_eq!(verify_at(&secret, &code, NOW), None);
+        }
+    }
+
+    #[test]
+    fn ignores_surrounding_spaces_and_rejects_wrong_codes() {
+        let secret = generate_secret();
+        let code = code_at(&secret, NOW);
+        assert_eq!(
+            verify_at(&secret, &format!(" {code} "), NOW),
+            Some((NOW / 30) as i64)
+        );
+        assert_eq!(verify_at(&secret, &code[..5], NOW), None);
+        assert_eq!(verify_at(&secret, "abcdef", NOW), None);
+    }
+
+    #[test]
+    fn rejects_invalid_secrets() {
+        assert_eq!(verify_at("no es base32!", "123456", NOW), None);
+    }
+}
@@ -0,0 +1,7 @@
+pub fn is_valid_phone(phone: &str) -> bool {
+    let digits = phone.chars().filter(|c| c.is_ascii_digit()).count();
+    phone
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '+' | '(' | ')'))
+        && (3..=15).contains(&digits)
+}
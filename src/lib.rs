@@ -1,6 +1,7 @@
 pub mod controllers;
 pub mod errors;
 pub mod helpers;
+pub mod mail;
 pub mod middlewares;
 pub mod models;
 pub mod repository;
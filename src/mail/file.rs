@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use axum::async_trait;
+use uuid::Uuid;
+
+use super::{MailError, MailMessage, Mailer};
+
+// Para desarrollo: cada correo se guarda como archivo de texto. El contenido no va al log
+// porque puede llevar enlaces con tokens vigentes
+pub struct FileMailer {
+    directory: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), MailError> {
+        tokio::fs::create_dir_all(&self.directory)
+            .await
+            .map_err(|e| MailError::Delivery(e.to_string()))?;
+
+        let content = format!(
+            "To: {}\nSubject: {}\n\n{}\n",
+            message.to, message.subject, message.body
+        );
+        let path = self.directory.join(format!(
+            "{}-{}.txt",
+            chrono::Local::now().format("%Y%m%d%H%M%S"),
+            Uuid::new_v4()
+        ));
+        tokio::fs::write(&path, &content)
+            .await
+            .map_err(|e| MailError::Delivery(e.to_string()))?;
+
+        tracing::info!("Correo para {} guardado en {}", message.to, path.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use axum::async_trait;
+
+pub mod file;
+pub mod smtp;
+
+pub use file::FileMailer;
+pub use smtp::SmtpMailer;
+
+#[derive(Debug, Clone)]
+pub struct MailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub enum MailError {
+    InvalidAddress(String),
+    Delivery(String),
+}
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailError::InvalidAddress(e) => write!(f, "Dirección de correo inválida: {}", e),
+            MailError::Delivery(e) => write!(f, "No se pudo enviar el correo: {}", e),
+        }
+    }
+}
+
+// Envío de correos; la implementación se elige al iniciar la aplicación
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, message: MailMessage) -> Result<(), MailError>;
+}
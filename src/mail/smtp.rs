@@ -0,0 +1,56 @@
+use axum::async_trait;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use super::{MailError, MailMessage, Mailer};
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    // Conexión con STARTTLS al servidor indicado
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: String,
+        password: String,
+        from: &str,
+    ) -> Result<Self, MailError> {
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(|e| MailError::Delivery(e.to_string()))?
+            .port(port)
+            .credentials(Credentials::new(username, password))
+            .build();
+        let from = from
+            .parse()
+            .map_err(|e: lettre::address::AddressError| MailError::InvalidAddress(e.to_string()))?;
+
+        Ok(Self { transport, from })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: MailMessage) -> Result<(), MailError> {
+        let to: Mailbox = message
+            .to
+            .parse()
+            .map_err(|e: lettre::address::AddressError| MailError::InvalidAddress(e.to_string()))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject)
+            .body(message.body)
+            .map_err(|e| MailError::Delivery(e.to_string()))?;
+
+        self.transport
+            .send(email)
+            .await
+            .map_err(|e| MailError::Delivery(e.to_string()))?;
+        Ok(())
+    }
+}
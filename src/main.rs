@@ -1,21 +1,23 @@
 use std::sync::Arc;
 
 use axum::{
+    handler::Handler,
     http::{self, HeaderValue, Method},
     middleware,
     routing::{delete, get, patch, post},
     Router,
 };
-use axum_extra::headers::Origin;
 use jsonwebtoken::{DecodingKey, EncodingKey};
 use shuttle_runtime::SecretStore;
 use sqlx::{self};
 
 use mvd_shuttle::{
-    controllers, middlewares,
+    controllers,
+    mail::{FileMailer, Mailer, SmtpMailer},
+    middlewares::{self, permission::RequiredPermission},
     utils::{AppState, Keys},
 };
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::CorsLayer;
 #[shuttle_runtime::main]
 async fn main(
     #[shuttle_runtime::Secrets] secrets: SecretStore,
@@ -37,6 +39,51 @@ async fn main(
         .get("JWT_SECRET")
         .expect("JWT_SECRET no está definido en Secrets.toml");
 
+    // El registro público queda deshabilitado salvo que se habilite explícitamente
+    let allow_registration = secrets
+        .get("ALLOW_REGISTRATION")
+        .is_some_and(|value| value == "true");
+
+    // Proxies delante del servidor que agregan X-Forwarded-For (en Shuttle, su proxy de entrada)
+    let trusted_proxy_hops = secrets
+        .get("TRUSTED_PROXY_HOPS")
+        .map(|hops| hops.parse().expect("TRUSTED_PROXY_HOPS debe ser un número"))
+        .unwrap_or(1);
+
+    let frontend_url = secrets
+        .get("FRONTEND_URL")
+        .expect("FRONTEND_URL no está definido en Secrets.toml");
+
+    // Correo: SMTP en producción, archivos locales en desarrollo. Se elige explícitamente para
+    // que un secreto faltante en producción no termine guardando los correos en disco
+    let mailer: Arc<dyn Mailer> = match secrets.get("MAIL_TRANSPORT").as_deref() {
+        Some("smtp") => Arc::new(
+            SmtpMailer::new(
+                &secrets
+                    .get("SMTP_HOST")
+                    .expect("SMTP_HOST no está definido en Secrets.toml"),
+                secrets
+                    .get("SMTP_PORT")
+                    .and_then(|port| port.parse().ok())
+                    .unwrap_or(587),
+                secrets.get("SMTP_USERNAME").unwrap_or_default(),
+                secrets.get("SMTP_PASSWORD").unwrap_or_default(),
+                &secrets
+                    .get("MAIL_FROM")
+                    .expect("MAIL_FROM no está definido en Secrets.toml"),
+            )
+            .expect("Configuración SMTP inválida"),
+        ),
+        // Los correos quedan en disco, con enlaces y tokens vigentes: solo para desarrollo
+        Some("file") => Arc::new(FileMailer::new(
+            secrets
+                .get("MAIL_DIR")
+                .unwrap_or_else(|| "mail".to_string()),
+        )),
+        Some(other) => panic!("MAIL_TRANSPORT \"{}\" no es válido (smtp o file)", other),
+        None => panic!("MAIL_TRANSPORT no está definido en Secrets.toml (smtp o file)"),
+    };
+
     // Crear el estado de la aplicación
     let state = AppState {
         pool,
@@ -44,49 +91,279 @@ async fn main(
             encoding: Arc::new(EncodingKey::from_secret(jwt_secret.as_bytes())),
             decoding: Arc::new(DecodingKey::from_secret(jwt_secret.as_bytes())),
         },
+        allow_registration,
+        trusted_proxy_hops,
+        frontend_url: frontend_url.clone(),
+        mailer,
     };
 
-    let frontend_url = secrets
-        .get("FRONTEND_URL")
-        .expect("FRONTEND_URL no está definido en Secrets.toml");
-
     // Configura CORS
     let cors = CorsLayer::new()
         .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::PATCH]) // Métodos permitidos
         .allow_origin(frontend_url.parse::<HeaderValue>().unwrap())
-        .allow_headers([http::header::CONTENT_TYPE])
+        // Authorization para los clientes que mandan el JWT como Bearer en lugar de la cookie
+        .allow_headers([http::header::CONTENT_TYPE, http::header::AUTHORIZATION])
         .allow_credentials(true);
 
+    // Cada ruta declara el permiso que necesita; las que no lo hacen son públicas a propósito:
+    // el login y la recuperación de contraseña, y el catálogo que la web del club muestra a
+    // cualquiera (espacios, actividades, horario semanal, disponibilidad y cotización de
+    // alquileres). Esas consultas públicas no devuelven datos de socios ni de quien alquila
+    let require = |permission: &'static str| {
+        middleware::from_fn_with_state(
+            RequiredPermission::new(&state, permission),
+            middlewares::permission::permission_middleware,
+        )
+    };
+
     // Configurar la aplicación
     let app = Router::new()
         .route("/api/v1/user/login", post(controllers::users::login))
         .route("/api/v1/user/register", post(controllers::users::register))
-        .route("/api/v1/space/create", post(controllers::spaces::create))
+        .route("/api/v1/user/refresh", post(controllers::users::refresh))
+        .route("/api/v1/user/logout", post(controllers::users::logout))
+        .route(
+            "/api/v1/user/login/2fa",
+            post(controllers::users::login_two_factor),
+        )
+        .route(
+            "/api/v1/user/2fa/setup",
+            post(controllers::two_factor::setup),
+        )
+        .route(
+            "/api/v1/user/2fa/confirm",
+            post(controllers::two_factor::confirm),
+        )
+        .route(
+            "/api/v1/user/2fa/disable",
+            post(controllers::two_factor::disable),
+        )
+        .route(
+            "/api/v1/user/password/forgot",
+            post(controllers::user_management::forgot_password),
+        )
+        .route(
+            "/api/v1/user/password/reset",
+            post(controllers::user_management::reset_password),
+        )
+        .route(
+            "/api/v1/users",
+            get(controllers::user_management::find_all.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/users/:uuid/2fa/reset",
+            post(controllers::user_management::reset_two_factor.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/roles",
+            get(controllers::user_management::find_roles.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/roles/:name/two_factor",
+            patch(
+                controllers::user_management::update_role_two_factor.layer(require("users:manage")),
+            ),
+        )
+        .route(
+            "/api/v1/users/lockouts",
+            get(controllers::user_management::find_lockouts.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/users/lockouts/:uuid",
+            delete(controllers::user_management::clear_lockout.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/users/invite",
+            post(controllers::user_management::invite.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/users/:uuid/role",
+            patch(controllers::user_management::update_role.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/users/:uuid/disable",
+            post(controllers::user_management::disable.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/users/:uuid/enable",
+            post(controllers::user_management::enable.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/users/:uuid/password_reset",
+            post(controllers::user_management::force_password_reset.layer(require("users:manage"))),
+        )
+        .route(
+            "/api/v1/space/create",
+            post(controllers::spaces::create.layer(require("spaces:write"))),
+        )
         .route("/api/v1/space/find", get(controllers::spaces::find_by_name))
+        .route("/api/v1/space", get(controllers::spaces::find_all))
+        .route(
+            "/api/v1/space/:uuid",
+            get(controllers::spaces::find_one)
+                .patch(controllers::spaces::update.layer(require("spaces:write")))
+                .delete(controllers::spaces::retire.layer(require("spaces:write"))),
+        )
+        .route(
+            "/api/v1/space/:uuid/availability",
+            get(controllers::spaces::availability),
+        )
         .route(
             "/api/v1/medical_societies",
-            get(controllers::medical_society::get_medical_societies).layer(
-                middleware::from_fn_with_state(
-                    state.clone(),
-                    middlewares::auth_middleware::auth_middleware,
+            get(controllers::medical_society::get_medical_societies
+                .layer(require("medical_societies:read")))
+            .post(controllers::medical_society::create.layer(require("medical_societies:write"))),
+        )
+        .route(
+            "/api/v1/medical_societies/coverage",
+            get(controllers::medical_society::coverage.layer(require("medical_societies:read"))),
+        )
+        .route(
+            "/api/v1/medical_societies/:uuid",
+            patch(controllers::medical_society::update.layer(require("medical_societies:write")))
+                .delete(
+                    controllers::medical_society::delete.layer(require("medical_societies:write")),
                 ),
-            ),
         )
-        .route("/api/v1/members/create", post(controllers::members::create))
         .route(
-            "/api/v1/members/find",
-            get(controllers::members::get_match_by_name),
+            "/api/v1/members/create",
+            post(controllers::members::create.layer(require("members:write"))),
+        )
+        .route(
+            "/api/v1/members/export",
+            get(controllers::members::export.layer(require("members:read"))),
+        )
+        .route(
+            "/api/v1/members/import",
+            post(controllers::member_import::import.layer(require("members:write"))),
+        )
+        .route(
+            "/api/v1/members/search",
+            get(controllers::members::search.layer(require("members:read"))),
+        )
+        .route(
+            "/api/v1/members",
+            get(controllers::members::find_all.layer(require("members:read"))),
+        )
+        .route(
+            "/api/v1/members/emergency_card",
+            get(controllers::members::emergency_card.layer(require("members:emergency_card"))),
         )
-        //.route("/api/v1/members/:uuid", get(controllers::members::find_one))
-        .route("/api/v1/members", get(controllers::members::find_all))
-        //.route("/api/v1/members/:uuid", patch(controllers::members::update))
         .route(
             "/api/v1/members/:uuid",
-            delete(controllers::members::delete),
+            get(controllers::members::find_one.layer(require("members:read")))
+                .patch(controllers::members::update.layer(require("members:write")))
+                .delete(controllers::members::delete.layer(require("members:write"))),
+        )
+        .route(
+            "/api/v1/members/:uuid/dues/unpaid",
+            get(controllers::dues::find_member_unpaid.layer(require("dues:read"))),
+        )
+        .route(
+            "/api/v1/members/:uuid/activities",
+            get(controllers::activities::find_by_member.layer(require("activities:read"))),
+        )
+        .route(
+            "/api/v1/activities",
+            get(controllers::activities::find_all)
+                .post(controllers::activities::create.layer(require("activities:write"))),
+        )
+        .route(
+            "/api/v1/activities/:uuid",
+            get(controllers::activities::find_one)
+                .patch(controllers::activities::update.layer(require("activities:write")))
+                .delete(controllers::activities::retire.layer(require("activities:write"))),
+        )
+        .route(
+            "/api/v1/activities/:uuid/members",
+            get(controllers::activities::find_roster.layer(require("activities:read")))
+                .post(controllers::activities::enroll.layer(require("activities:enroll"))),
+        )
+        .route(
+            "/api/v1/activities/:uuid/members/:member_id",
+            delete(controllers::activities::unenroll.layer(require("activities:enroll"))),
+        )
+        .route(
+            "/api/v1/schedule",
+            get(controllers::schedule::week)
+                .post(controllers::schedule::create.layer(require("schedule:write"))),
+        )
+        .route(
+            "/api/v1/schedule/:uuid",
+            get(controllers::schedule::find_one)
+                .patch(controllers::schedule::update.layer(require("schedule:write")))
+                .delete(controllers::schedule::delete.layer(require("schedule:write"))),
+        )
+        .route(
+            "/api/v1/rents",
+            get(controllers::rents::find_all.layer(require("rents:read")))
+                .post(controllers::rents::create.layer(require("rents:write"))),
+        )
+        .route(
+            "/api/v1/rents/export",
+            get(controllers::rents::export.layer(require("rents:read"))),
+        )
+        .route("/api/v1/rents/quote", post(controllers::rents::quote))
+        .route(
+            "/api/v1/rents/:uuid",
+            get(controllers::rents::find_one.layer(require("rents:read")))
+                .delete(controllers::rents::cancel.layer(require("rents:write"))),
+        )
+        .route(
+            "/api/v1/rents/:uuid/payment",
+            post(controllers::rents::register_payment.layer(require("rents:collect"))),
+        )
+        .route(
+            "/api/v1/employees",
+            get(controllers::employees::find_all.layer(require("employees:read")))
+                .post(controllers::employees::create.layer(require("employees:write"))),
+        )
+        .route(
+            "/api/v1/employees/:uuid",
+            get(controllers::employees::find_one.layer(require("employees:read")))
+                .patch(controllers::employees::update.layer(require("employees:write")))
+                .delete(controllers::employees::deactivate.layer(require("employees:write"))),
+        )
+        .route(
+            "/api/v1/employees/:uuid/payments",
+            get(controllers::payroll::find_by_employee.layer(require("payroll:read"))),
+        )
+        .route(
+            "/api/v1/payroll/run",
+            post(controllers::payroll::payroll_run.layer(require("payroll:run"))),
+        )
+        .route(
+            "/api/v1/payroll/summary",
+            get(controllers::payroll::summary.layer(require("payroll:read"))),
+        )
+        .route(
+            "/api/v1/payroll/:uuid/payment",
+            post(controllers::payroll::register_payment.layer(require("payroll:run"))),
+        )
+        .route(
+            "/api/v1/dues",
+            get(controllers::dues::find_all.layer(require("dues:read"))),
+        )
+        .route(
+            "/api/v1/dues/export",
+            get(controllers::dues::export.layer(require("dues:read"))),
+        )
+        .route(
+            "/api/v1/dues/billing_run",
+            post(controllers::dues::billing_run.layer(require("dues:bill"))),
+        )
+        .route(
+            "/api/v1/dues/unpaid",
+            get(controllers::dues::find_unpaid.layer(require("dues:read"))),
+        )
+        .route(
+            "/api/v1/dues/arrears",
+            get(controllers::dues::arrears.layer(require("dues:read"))),
         )
         .route(
-            "/api/v1/members/find_by_name",
-            get(controllers::members::get_match_by_name),
+            "/api/v1/dues/:uuid/payments",
+            get(controllers::dues::find_payments.layer(require("dues:read")))
+                .post(controllers::dues::register_payment.layer(require("dues:collect"))),
         )
         .layer(cors)
         .with_state(state); // Pasar el estado a los manejadores
@@ -1,47 +1,69 @@
 use axum::{
-    extract::{Request, State},
-    middleware::Next,
-    response::Response,
+    async_trait,
+    extract::{FromRef, FromRequestParts, State},
+    http::request::Parts,
 };
 use jsonwebtoken::{decode, Validation};
 
 use crate::{
     errors::AuthError,
+    helpers::cookies::{self, ACCESS_TOKEN_COOKIE},
     repository::UserRepository,
-    utils::{AppState, Claims},
+    utils::{AppState, Claims, User},
 };
 
-pub async fn jwt_middleware(
-    State(state): State<AppState>,
-    mut request: Request,
-    next: Next,
-) -> Result<Response, AuthError> {
-    // Extraer el token del encabezado Authorization
-    let token = request
-        .headers()
+// Usuario autenticado: acepta el JWT en el encabezado Authorization o en la cookie accessToken
+#[derive(Debug, Clone)]
+pub struct AuthUser {
+    pub user: User,
+    pub claims: Claims,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthUser
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        // Si otro extractor ya autenticó esta solicitud se reutiliza el resultado
+        if let Some(auth_user) = parts.extensions.get::<AuthUser>() {
+            return Ok(auth_user.clone());
+        }
+
+        let state = AppState::from_ref(state);
+        let token = bearer_token(parts)
+            .or_else(|| cookies::find_cookie(&parts.headers, ACCESS_TOKEN_COOKIE))
+            .ok_or(AuthError::MissingToken)?;
+
+        // Decodificar el JWT
+        let claims = decode::<Claims>(&token, &state.jwt_secret.decoding, &Validation::default())
+            .map_err(|_| AuthError::InvalidToken)?
+            .claims;
+
+        let user = UserRepository::find_by_email(State(state), &claims.sub)
+            .await
+            .map_err(AuthError::DatabaseError)?
+            .ok_or(AuthError::InvalidToken)?;
+
+        // Una cuenta deshabilitada no puede usar los tokens que ya tenía
+        if user.disabled_at.is_some() {
+            return Err(AuthError::AccountDisabled);
+        }
+
+        let auth_user = AuthUser { user, claims };
+        parts.extensions.insert(auth_user.clone());
+        Ok(auth_user)
+    }
+}
+
+fn bearer_token(parts: &Parts) -> Option<String> {
+    parts
+        .headers
         .get("Authorization")
         .and_then(|header| header.to_str().ok())
         .and_then(|header| header.strip_prefix("Bearer "))
-        .ok_or(AuthError::MissingToken)?;
-
-    // Decodificar el JWT
-    let decoding_key = state.jwt_secret.decoding.clone();
-    let validation = Validation::default();
-
-    match decode::<Claims>(token, &decoding_key, &validation) {
-        Ok(token_data) => {
-            // Insertar los claims en la solicitud para su uso posterior,
-            let claims = token_data.claims.clone();
-            let user =
-                UserRepository::find_by_email(axum::extract::State(state.clone()), &claims.sub)
-                    .await;
-            if let None = user {
-                return Err(AuthError::InvalidToken);
-            } else {
-                request.extensions_mut().insert((user, claims));
-                Ok(next.run(request).await)
-            }
-        }
-        Err(_) => Err(AuthError::InvalidToken),
-    }
+        .map(|token| token.trim().to_string())
 }
@@ -0,0 +1,57 @@
+use axum::{
+    extract::{FromRef, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{errors::AuthError, repository::role::RoleRepository, utils::AppState};
+
+use super::auth::AuthUser;
+
+// Estado del middleware: el permiso que exige la ruta
+#[derive(Clone)]
+pub struct RequiredPermission {
+    pub state: AppState,
+    pub permission: &'static str,
+}
+
+impl RequiredPermission {
+    pub fn new(state: &AppState, permission: &'static str) -> Self {
+        Self {
+            state: state.clone(),
+            permission,
+        }
+    }
+}
+
+impl FromRef<RequiredPermission> for AppState {
+    fn from_ref(required: &RequiredPermission) -> Self {
+        required.state.clone()
+    }
+}
+
+pub async fn permission_middleware(
+    State(required): State<RequiredPermission>,
+    auth_user: AuthUser,
+    request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    // Se consulta el rol guardado en la base y no el del token, para que un cambio de rol aplique enseguida
+    let access = RoleRepository::find_access(
+        &required.state.pool,
+        &auth_user.user.rolename,
+        required.permission,
+    )
+    .await
+    .map_err(AuthError::DatabaseError)?
+    .ok_or(AuthError::Forbidden)?;
+
+    if !access.has_permission {
+        return Err(AuthError::Forbidden);
+    }
+    if access.require_two_factor && auth_user.user.totp_enabled_at.is_none() {
+        return Err(AuthError::TwoFactorEnrollmentRequired);
+    }
+
+    Ok(next.run(request).await)
+}
@@ -0,0 +1,25 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Activity {
+    pub id: Uuid,
+    pub name: String,
+    pub category: Option<String>,
+    pub retired_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Socio inscripto en una actividad
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ActivityMember {
+    pub id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub phone: String,
+    pub enrolled_at: NaiveDateTime,
+}
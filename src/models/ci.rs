@@ -0,0 +1,123 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+// Pesos del dígito verificador de la cédula uruguaya, para los 7 dígitos del número
+const CHECK_WEIGHTS: [u32; 7] = [2, 9, 8, 7, 6, 3, 4];
+
+// Cédula de identidad uruguaya normalizada: solo dígitos, con el verificador al final.
+// En la base se guarda como texto; al leerla no se vuelve a validar
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(try_from = "String", into = "String")]
+#[sqlx(transparent)]
+pub struct Ci(String);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CiError {
+    InvalidFormat,
+    InvalidCheckDigit,
+}
+
+impl fmt::Display for CiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CiError::InvalidFormat => write!(f, "La cédula debe tener entre 6 y 8 dígitos"),
+            CiError::InvalidCheckDigit => {
+                write!(f, "El dígito verificador de la cédula no es válido")
+            }
+        }
+    }
+}
+
+impl Ci {
+    // Acepta "1.234.567-2", "1234567-2" o "12345672"
+    pub fn parse(input: &str) -> Result<Self, CiError> {
+        let digits: String = input
+            .chars()
+            .filter(|c| !matches!(c, '.' | '-' | ' '))
+            .collect();
+        if !(6..=8).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(CiError::InvalidFormat);
+        }
+
+        let (number, check) = digits.split_at(digits.len() - 1);
+        if Self::check_digit(number) != check.parse::<u32>().unwrap_or(u32::MAX) {
+            return Err(CiError::InvalidCheckDigit);
+        }
+        Ok(Self(digits))
+    }
+
+    // Cada dígito (completado con ceros a la izquierda hasta 7) se multiplica por su peso;
+    // el verificador es lo que le falta a la suma para llegar a la siguiente decena
+    fn check_digit(number: &str) -> u32 {
+        let padded = format!("{:0>7}", number);
+        let sum: u32 = padded
+            .chars()
+            .zip(CHECK_WEIGHTS)
+            .map(|(digit, weight)| digit.to_digit(10).unwrap_or(0) * weight)
+            .sum();
+        (10 - sum % 10) % 10
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Ci {
+    type Err = CiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for Ci {
+    type Error = CiError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<Ci> for String {
+    fn from(ci: Ci) -> Self {
+        ci.0
+    }
+}
+
+impl fmt::Display for Ci {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_formatted_ci() {
+        assert_eq!(Ci::parse("1.234.567-2").unwrap().as_str(), "12345672");
+        assert_eq!(Ci::parse("1234567-2").unwrap().as_str(), "12345672");
+    }
+
+    #[test]
+    fn rejects_wrong_check_digit() {
+        assert_eq!(Ci::parse("12345678"), Err(CiError::InvalidCheckDigit));
+    }
+
+    #[test]
+    fn accepts_between_6_and_8_digits() {
+        assert_eq!(Ci::parse("12345"), Err(CiError::InvalidFormat));
+        assert!(Ci::parse("123458").is_ok());
+        assert!(Ci::parse("1234561").is_ok());
+        assert!(Ci::parse("12345672").is_ok());
+        assert_eq!(Ci::parse("123456789"), Err(CiError::InvalidFormat));
+    }
+
+    #[test]
+    fn rejects_non_digits() {
+        assert_eq!(Ci::parse("1234a672"), Err(CiError::InvalidFormat));
+    }
+}
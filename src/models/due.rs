@@ -0,0 +1,69 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Due {
+    pub id: Uuid,
+    pub member_id: Uuid,
+    pub amount: Decimal,
+    pub amount_paid: Decimal,
+    pub payment_date: Option<NaiveDate>,
+    pub month: i32,
+    pub year: i32,
+    pub is_payed: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct DuePayment {
+    pub id: Uuid,
+    pub due_id: Uuid,
+    pub amount: Decimal,
+    pub payment_date: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Cuota con los datos del socio, para el listado filtrado y la exportación
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct DueListItem {
+    pub id: Uuid,
+    pub member_id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub amount: Decimal,
+    pub amount_paid: Decimal,
+    pub month: i32,
+    pub year: i32,
+    pub is_payed: bool,
+    pub payment_date: Option<NaiveDate>,
+}
+
+// Cuota impaga junto con los datos del socio que la debe
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct UnpaidDue {
+    pub id: Uuid,
+    pub member_id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub amount: Decimal,
+    pub amount_paid: Decimal,
+    pub month: i32,
+    pub year: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Arrears {
+    pub member_id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub months_owed: i64,
+    pub total_owed: Decimal,
+}
@@ -0,0 +1,59 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+use super::ci::Ci;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Employee {
+    pub id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: Ci,
+    pub phone: String,
+    pub email: Option<String>,
+    pub address: String,
+    pub position: String,
+    pub hire_date: NaiveDate,
+    pub salary: Option<Decimal>,
+    pub medical_society_id: Uuid,
+    pub is_active: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct EmployeePayment {
+    pub id: Uuid,
+    pub employee_id: Uuid,
+    pub amount: Decimal,
+    pub payment_date: Option<NaiveDate>,
+    pub month: i32,
+    pub year: i32,
+    pub is_payed: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Pago de sueldo con los datos del funcionario, para conciliar la liquidación
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PayrollLine {
+    pub id: Uuid,
+    pub employee_id: Uuid,
+    pub name: String,
+    pub lastname: String,
+    pub ci: String,
+    pub amount: Decimal,
+    pub payment_date: Option<NaiveDate>,
+    pub is_payed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PayrollTotal {
+    pub employees: i64,
+    pub total: Decimal,
+    pub paid: Decimal,
+    pub pending: Decimal,
+}
@@ -1 +1,7 @@
+pub mod activity;
+pub mod ci;
+pub mod due;
+pub mod employee;
+pub mod rent;
+pub mod schedule;
 pub mod space;
@@ -0,0 +1,37 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Rent {
+    pub id: Uuid,
+    pub full_name: String,
+    pub phone: String,
+    pub space_id: Uuid,
+    pub rent_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub cost: Decimal,
+    pub is_payed: bool,
+    pub payment_date: Option<NaiveDate>,
+    pub cancelled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Alquiler con el nombre del espacio, para la exportación
+#[derive(Debug, FromRow)]
+pub struct RentExportRow {
+    pub rent_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub space_name: String,
+    pub full_name: String,
+    pub phone: String,
+    pub cost: Decimal,
+    pub is_payed: bool,
+    pub payment_date: Option<NaiveDate>,
+    pub cancelled_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,60 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use uuid::Uuid;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type,
+)]
+#[sqlx(type_name = "weekday", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Lunes,
+    Martes,
+    Miercoles,
+    Jueves,
+    Viernes,
+    Sabado,
+    Domingo,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Lunes,
+        Weekday::Martes,
+        Weekday::Miercoles,
+        Weekday::Jueves,
+        Weekday::Viernes,
+        Weekday::Sabado,
+        Weekday::Domingo,
+    ];
+
+    pub fn of(date: NaiveDate) -> Self {
+        Self::ALL[date.weekday().num_days_from_monday() as usize]
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ScheduleSlot {
+    pub id: Uuid,
+    pub activity_id: Uuid,
+    pub day: Weekday,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub space_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+// Horario con los nombres de la actividad y del espacio, para las vistas
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ScheduleSlotDetail {
+    pub id: Uuid,
+    pub activity_id: Uuid,
+    pub activity_name: String,
+    pub day: Weekday,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub space_id: Uuid,
+    pub space_name: String,
+}
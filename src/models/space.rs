@@ -1,12 +1,38 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use uuid::Uuid;
 
+use super::schedule::Weekday;
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Space {
     pub id: Uuid,
     pub name: String,
+    pub capacity: Option<i32>,
+    pub is_indoor: bool,
+    pub hourly_price: Option<Decimal>,
+    pub retired_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OpeningHours {
+    pub day: Weekday,
+    pub opens_at: NaiveTime,
+    pub closes_at: NaiveTime,
+}
+
+// Horario con el que se crea un espacio si no se indica otro
+pub fn default_opening_hours() -> Vec<OpeningHours> {
+    Weekday::ALL
+        .iter()
+        .map(|day| OpeningHours {
+            day: *day,
+            opens_at: NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+            closes_at: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+        })
+        .collect()
+}
@@ -0,0 +1,172 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::activity::{Activity, ActivityMember};
+
+pub struct ActivityRepository;
+
+impl ActivityRepository {
+    pub async fn create(
+        pool: &PgPool,
+        name: &str,
+        category: Option<&str>,
+    ) -> Result<Activity, sqlx::Error> {
+        sqlx::query_as::<_, Activity>(
+            r#"
+            INSERT INTO activities (name, category)
+            VALUES ($1, $2)
+            RETURNING id, name, category, retired_at, created_at, updated_at
+            "#,
+        )
+        .bind(name)
+        .bind(category)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn find_all(
+        pool: &PgPool,
+        include_retired: bool,
+    ) -> Result<Vec<Activity>, sqlx::Error> {
+        sqlx::query_as::<_, Activity>(
+            r#"
+            SELECT id, name, category, retired_at, created_at, updated_at
+            FROM activities
+            WHERE $1 OR retired_at IS NULL
+            ORDER BY name
+            "#,
+        )
+        .bind(include_retired)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Activity>, sqlx::Error> {
+        sqlx::query_as::<_, Activity>(
+            r#"
+            SELECT id, name, category, retired_at, created_at, updated_at
+            FROM activities
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn find_by_name(pool: &PgPool, name: &str) -> Result<Option<Activity>, sqlx::Error> {
+        sqlx::query_as::<_, Activity>(
+            r#"
+            SELECT id, name, category, retired_at, created_at, updated_at
+            FROM activities
+            WHERE lower(name) = lower($1) AND retired_at IS NULL
+            "#,
+        )
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn update(pool: &PgPool, activity: &Activity) -> Result<Activity, sqlx::Error> {
+        sqlx::query_as::<_, Activity>(
+            r#"
+            UPDATE activities
+            SET name = $1, category = $2, updated_at = current_timestamp
+            WHERE id = $3
+            RETURNING id, name, category, retired_at, created_at, updated_at
+            "#,
+        )
+        .bind(&activity.name)
+        .bind(&activity.category)
+        .bind(activity.id)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn retire(pool: &PgPool, id: Uuid) -> Result<Option<Activity>, sqlx::Error> {
+        sqlx::query_as::<_, Activity>(
+            r#"
+            UPDATE activities
+            SET retired_at = current_timestamp, updated_at = current_timestamp
+            WHERE id = $1 AND retired_at IS NULL
+            RETURNING id, name, category, retired_at, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn enroll(
+        pool: &PgPool,
+        activity_id: Uuid,
+        member_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO members_activities (member_id, activity_id)
+            VALUES ($1, $2)
+            "#,
+        )
+        .bind(member_id)
+        .bind(activity_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    // Devuelve false si el socio no estaba inscripto
+    pub async fn unenroll(
+        pool: &PgPool,
+        activity_id: Uuid,
+        member_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM members_activities
+            WHERE member_id = $1 AND activity_id = $2
+            "#,
+        )
+        .bind(member_id)
+        .bind(activity_id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn find_roster(
+        pool: &PgPool,
+        activity_id: Uuid,
+    ) -> Result<Vec<ActivityMember>, sqlx::Error> {
+        sqlx::query_as::<_, ActivityMember>(
+            r#"
+            SELECT m.id, m.name, m.lastname, m.ci, m.phone, ma.created_at AS enrolled_at
+            FROM members_activities ma
+            INNER JOIN members m ON m.id = ma.member_id
+            WHERE ma.activity_id = $1
+            ORDER BY m.lastname, m.name
+            "#,
+        )
+        .bind(activity_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_member(
+        pool: &PgPool,
+        member_id: Uuid,
+    ) -> Result<Vec<Activity>, sqlx::Error> {
+        sqlx::query_as::<_, Activity>(
+            r#"
+            SELECT a.id, a.name, a.category, a.retired_at, a.created_at, a.updated_at
+            FROM activities a
+            INNER JOIN members_activities ma ON ma.activity_id = a.id
+            WHERE ma.member_id = $1
+            ORDER BY a.name
+            "#,
+        )
+        .bind(member_id)
+        .fetch_all(pool)
+        .await
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::NaiveDate;
+use futures_util::stream::BoxStream;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use sqlx::{postgres::PgArguments, query::QueryAs, PgConnection, PgPool, Postgres};
+use uuid::Uuid;
+
+use crate::models::due::{Arrears, Due, DueListItem, DuePayment, UnpaidDue};
+
+pub struct DueRepository;
+
+// Filtros del listado de cuotas; la exportación usa los mismos
+#[derive(Debug, Default, Deserialize)]
+pub struct DueFilter {
+    pub member_id: Option<Uuid>,
+    pub year: Option<i32>,
+    pub month: Option<i32>,
+    pub is_payed: Option<bool>,
+}
+
+const DUE_LIST_SQL: &str = r#"
+    SELECT d.id, d.member_id, m.name, m.lastname, m.ci, d.amount, d.amount_paid, d.month, d.year, d.is_payed, d.payment_date
+    FROM dues d
+    INNER JOIN members m ON m.id = d.member_id
+    WHERE ($1::uuid IS NULL OR d.member_id = $1)
+      AND ($2::int IS NULL OR d.year = $2)
+      AND ($3::int IS NULL OR d.month = $3)
+      AND ($4::boolean IS NULL OR d.is_payed = $4)
+    ORDER BY d.year DESC, d.month DESC, m.lastname, m.name, d.id
+"#;
+
+fn due_list_query(filter: &DueFilter) -> QueryAs<'static, Postgres, DueListItem, PgArguments> {
+    sqlx::query_as::<_, DueListItem>(DUE_LIST_SQL)
+        .bind(filter.member_id)
+        .bind(filter.year)
+        .bind(filter.month)
+        .bind(filter.is_payed)
+}
+
+impl DueRepository {
+    // Genera la cuota del mes para cada socio activo que todavía no la tenga
+    pub async fn billing_run(
+        pool: &PgPool,
+        month: i32,
+        year: i32,
+        amount: Decimal,
+    ) -> Result<Vec<Due>, sqlx::Error> {
+        let dues = sqlx::query_as::<_, Due>(
+            r#"
+            INSERT INTO dues (member_id, amount, month, year)
+            SELECT id, $1, $2, $3
+            FROM members
+            WHERE is_active
+            ON CONFLICT (member_id, month, year) DO NOTHING
+            RETURNING id, member_id, amount, amount_paid, payment_date, month, year, is_payed, created_at, updated_at
+            "#,
+        )
+        .bind(amount)
+        .bind(month)
+        .bind(year)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(dues)
+    }
+
+    pub async fn find_for_update(
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<Due>, sqlx::Error> {
+        sqlx::query_as::<_, Due>(
+            r#"
+            SELECT id, member_id, amount, amount_paid, payment_date, month, year, is_payed, created_at, updated_at
+            FROM dues
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+    }
+
+    pub async fn add_payment(
+        conn: &mut PgConnection,
+        due_id: Uuid,
+        amount: Decimal,
+        payment_date: NaiveDate,
+    ) -> Result<(Due, DuePayment), sqlx::Error> {
+        let payment = sqlx::query_as::<_, DuePayment>(
+            r#"
+            INSERT INTO dues_payments (due_id, amount, payment_date)
+            VALUES ($1, $2, $3)
+            RETURNING id, due_id, amount, payment_date, created_at, updated_at
+            "#,
+        )
+        .bind(due_id)
+        .bind(amount)
+        .bind(payment_date)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        let due = sqlx::query_as::<_, Due>(
+            r#"
+            UPDATE dues
+            SET amount_paid = amount_paid + $1,
+                is_payed = amount_paid + $1 >= amount,
+                payment_date = $2,
+                updated_at = current_timestamp
+            WHERE id = $3
+            RETURNING id, member_id, amount, amount_paid, payment_date, month, year, is_payed, created_at, updated_at
+            "#,
+        )
+        .bind(amount)
+        .bind(payment_date)
+        .bind(due_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok((due, payment))
+    }
+
+    pub async fn find_payments(
+        pool: &PgPool,
+        due_id: Uuid,
+    ) -> Result<Vec<DuePayment>, sqlx::Error> {
+        sqlx::query_as::<_, DuePayment>(
+            r#"
+            SELECT id, due_id, amount, payment_date, created_at, updated_at
+            FROM dues_payments
+            WHERE due_id = $1
+            ORDER BY payment_date, created_at
+            "#,
+        )
+        .bind(due_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Cuotas con los datos del socio según los filtros, de la más reciente a la más antigua
+    pub async fn find_all(
+        pool: &PgPool,
+        filter: &DueFilter,
+    ) -> Result<Vec<DueListItem>, sqlx::Error> {
+        due_list_query(filter).fetch_all(pool).await
+    }
+
+    // Igual que find_all pero fila a fila, para exportar sin cargar todo en memoria
+    pub fn stream_all<'a>(
+        pool: &'a PgPool,
+        filter: &DueFilter,
+    ) -> BoxStream<'a, Result<DueListItem, sqlx::Error>> {
+        due_list_query(filter).fetch(pool)
+    }
+
+    // Cuotas impagas de todo el club, o de un socio si se indica `member_id`
+    pub async fn find_unpaid(
+        pool: &PgPool,
+        member_id: Option<Uuid>,
+    ) -> Result<Vec<UnpaidDue>, sqlx::Error> {
+        sqlx::query_as::<_, UnpaidDue>(
+            r#"
+            SELECT d.id, d.member_id, m.name, m.lastname, m.ci, d.amount, d.amount_paid, d.month, d.year
+            FROM dues d
+            INNER JOIN members m ON m.id = d.member_id
+            WHERE NOT d.is_payed AND ($1::uuid IS NULL OR d.member_id = $1)
+            ORDER BY m.lastname, m.name, d.year, d.month
+            "#,
+        )
+        .bind(member_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn arrears(pool: &PgPool) -> Result<Vec<Arrears>, sqlx::Error> {
+        sqlx::query_as::<_, Arrears>(
+            r#"
+            SELECT m.id AS member_id, m.name, m.lastname, m.ci,
+                   COUNT(d.id) AS months_owed,
+                   SUM(d.amount - d.amount_paid) AS total_owed
+            FROM dues d
+            INNER JOIN members m ON m.id = d.member_id
+            WHERE NOT d.is_payed
+            GROUP BY m.id, m.name, m.lastname, m.ci
+            ORDER BY months_owed DESC, total_owed DESC
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+}
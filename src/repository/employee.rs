@@ -0,0 +1,145 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::{ci::Ci, employee::Employee};
+
+pub struct EmployeeRepository;
+
+pub struct EmployeeData {
+    pub name: String,
+    pub lastname: String,
+    pub ci: Ci,
+    pub phone: String,
+    pub email: Option<String>,
+    pub address: String,
+    pub position: String,
+    pub hire_date: NaiveDate,
+    pub salary: Option<Decimal>,
+    pub medical_society_id: Uuid,
+    pub is_active: bool,
+}
+
+impl From<Employee> for EmployeeData {
+    fn from(employee: Employee) -> Self {
+        Self {
+            name: employee.name,
+            lastname: employee.lastname,
+            ci: employee.ci,
+            phone: employee.phone,
+            email: employee.email,
+            address: employee.address,
+            position: employee.position,
+            hire_date: employee.hire_date,
+            salary: employee.salary,
+            medical_society_id: employee.medical_society_id,
+            is_active: employee.is_active,
+        }
+    }
+}
+
+impl EmployeeRepository {
+    pub async fn create(pool: &PgPool, employee: &EmployeeData) -> Result<Employee, sqlx::Error> {
+        sqlx::query_as::<_, Employee>(
+            r#"
+            INSERT INTO employees (name, lastname, ci, phone, email, address, position, hire_date, salary, medical_society_id, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            RETURNING id, name, lastname, ci, phone, email, address, position, hire_date, salary, medical_society_id, is_active, created_at, updated_at
+            "#,
+        )
+        .bind(&employee.name)
+        .bind(&employee.lastname)
+        .bind(&employee.ci)
+        .bind(&employee.phone)
+        .bind(&employee.email)
+        .bind(&employee.address)
+        .bind(&employee.position)
+        .bind(employee.hire_date)
+        .bind(employee.salary)
+        .bind(employee.medical_society_id)
+        .bind(employee.is_active)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn find_all(
+        pool: &PgPool,
+        include_inactive: bool,
+    ) -> Result<Vec<Employee>, sqlx::Error> {
+        sqlx::query_as::<_, Employee>(
+            r#"
+            SELECT id, name, lastname, ci, phone, email, address, position, hire_date, salary, medical_society_id, is_active, created_at, updated_at
+            FROM employees
+            WHERE $1 OR is_active
+            ORDER BY lastname, name
+            "#,
+        )
+        .bind(include_inactive)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Employee>, sqlx::Error> {
+        sqlx::query_as::<_, Employee>(
+            r#"
+            SELECT id, name, lastname, ci, phone, email, address, position, hire_date, salary, medical_society_id, is_active, created_at, updated_at
+            FROM employees
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn update(
+        pool: &PgPool,
+        id: Uuid,
+        employee: &EmployeeData,
+    ) -> Result<Employee, sqlx::Error> {
+        sqlx::query_as::<_, Employee>(
+            r#"
+            UPDATE employees
+            SET name = $1, lastname = $2, ci = $3, phone = $4, email = $5, address = $6, position = $7, hire_date = $8, salary = $9, medical_society_id = $10, is_active = $11, updated_at = current_timestamp
+            WHERE id = $12
+            RETURNING id, name, lastname, ci, phone, email, address, position, hire_date, salary, medical_society_id, is_active, created_at, updated_at
+            "#,
+        )
+        .bind(&employee.name)
+        .bind(&employee.lastname)
+        .bind(&employee.ci)
+        .bind(&employee.phone)
+        .bind(&employee.email)
+        .bind(&employee.address)
+        .bind(&employee.position)
+        .bind(employee.hire_date)
+        .bind(employee.salary)
+        .bind(employee.medical_society_id)
+        .bind(employee.is_active)
+        .bind(id)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn find_id_by_ci(pool: &PgPool, ci: &Ci) -> Result<Option<Uuid>, sqlx::Error> {
+        sqlx::query_scalar("SELECT id FROM employees WHERE ci = $1")
+            .bind(ci)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn deactivate(pool: &PgPool, id: Uuid) -> Result<Option<Employee>, sqlx::Error> {
+        sqlx::query_as::<_, Employee>(
+            r#"
+            UPDATE employees
+            SET is_active = false, updated_at = current_timestamp
+            WHERE id = $1 AND is_active
+            RETURNING id, name, lastname, ci, phone, email, address, position, hire_date, salary, medical_society_id, is_active, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+}
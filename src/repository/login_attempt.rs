@@ -0,0 +1,191 @@
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use sqlx::{prelude::FromRow, PgPool};
+use uuid::Uuid;
+
+pub struct LoginAttemptRepository;
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct LoginAttempt {
+    pub id: Uuid,
+    pub kind: String,
+    pub key: String,
+    pub failed_count: i32,
+    pub last_failed_at: NaiveDateTime,
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct LoginLockout {
+    pub id: Uuid,
+    pub kind: String,
+    pub key: String,
+    pub failed_count: i32,
+    pub locked_until: NaiveDateTime,
+    pub cleared_at: Option<NaiveDateTime>,
+    pub cleared_by: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl LoginAttemptRepository {
+    pub async fn find(
+        pool: &PgPool,
+        kind: &str,
+        key: &str,
+    ) -> Result<Option<LoginAttempt>, sqlx::Error> {
+        sqlx::query_as::<_, LoginAttempt>(
+            r#"
+            SELECT id, kind, key, failed_count, last_failed_at, locked_until
+            FROM login_attempts
+            WHERE kind = $1 AND key = $2
+            "#,
+        )
+        .bind(kind)
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+    }
+
+    // Suma un fallo; si el último es anterior a la ventana el contador vuelve a empezar
+    pub async fn record_failure(
+        pool: &PgPool,
+        kind: &str,
+        key: &str,
+        window_secs: i64,
+    ) -> Result<LoginAttempt, sqlx::Error> {
+        sqlx::query_as::<_, LoginAttempt>(
+            r#"
+            INSERT INTO login_attempts (kind, key, failed_count, last_failed_at)
+            VALUES ($1, $2, 1, current_timestamp)
+            ON CONFLICT (kind, key) DO UPDATE
+            SET failed_count = CASE
+                    WHEN login_attempts.last_failed_at < current_timestamp - make_interval(secs => $3)
+                    THEN 1
+                    ELSE login_attempts.failed_count + 1
+                END,
+                last_failed_at = current_timestamp
+            RETURNING id, kind, key, failed_count, last_failed_at, locked_until
+            "#,
+        )
+        .bind(kind)
+        .bind(key)
+        .bind(window_secs as f64)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn set_locked_until(
+        pool: &PgPool,
+        id: Uuid,
+        locked_until: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE login_attempts
+            SET locked_until = $1
+            WHERE id = $2
+            "#,
+        )
+        .bind(locked_until)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_lockout(pool: &PgPool, attempt: &LoginAttempt) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO login_lockouts (kind, key, failed_count, locked_until)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(&attempt.kind)
+        .bind(&attempt.key)
+        .bind(attempt.failed_count)
+        .bind(attempt.locked_until)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn reset(pool: &PgPool, kind: &str, key: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM login_attempts
+            WHERE kind = $1 AND key = $2
+            "#,
+        )
+        .bind(kind)
+        .bind(key)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_active_lockouts(pool: &PgPool) -> Result<Vec<LoginAttempt>, sqlx::Error> {
+        sqlx::query_as::<_, LoginAttempt>(
+            r#"
+            SELECT id, kind, key, failed_count, last_failed_at, locked_until
+            FROM login_attempts
+            WHERE locked_until > current_timestamp
+            ORDER BY locked_until DESC
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_lockout_history(
+        pool: &PgPool,
+        limit: i64,
+    ) -> Result<Vec<LoginLockout>, sqlx::Error> {
+        sqlx::query_as::<_, LoginLockout>(
+            r#"
+            SELECT id, kind, key, failed_count, locked_until, cleared_at, cleared_by, created_at
+            FROM login_lockouts
+            ORDER BY created_at DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Levanta el bloqueo, reinicia el contador y deja constancia de quién lo hizo
+    pub async fn clear(
+        pool: &PgPool,
+        id: Uuid,
+        cleared_by: &str,
+    ) -> Result<Option<LoginAttempt>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let attempt = sqlx::query_as::<_, LoginAttempt>(
+            r#"
+            DELETE FROM login_attempts
+            WHERE id = $1
+            RETURNING id, kind, key, failed_count, last_failed_at, locked_until
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(attempt) = &attempt {
+            sqlx::query(
+                r#"
+                UPDATE login_lockouts
+                SET cleared_at = current_timestamp, cleared_by = $1
+                WHERE kind = $2 AND key = $3 AND cleared_at IS NULL
+                "#,
+            )
+            .bind(cleared_by)
+            .bind(&attempt.kind)
+            .bind(&attempt.key)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(attempt)
+    }
+}
@@ -1,14 +1,19 @@
 use axum::extract::State;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
 
 use crate::utils::{AppState, User};
 
 pub struct UserRepository;
 
 impl UserRepository {
-    pub async fn find_by_email(State(state): State<AppState>, email: &str) -> Option<User> {
-        match sqlx::query_as::<_, User>(
+    pub async fn find_by_email(
+        State(state): State<AppState>,
+        email: &str,
+    ) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
             r#"
-        SELECT id, name, rolename, email, password, created_at, updated_at
+        SELECT id, name, rolename, email, password, disabled_at, totp_secret, totp_enabled_at, created_at, updated_at
         FROM users
         WHERE email = $1
         "#,
@@ -16,29 +21,140 @@ impl UserRepository {
         .bind(email) // Bind del parámetro email
         .fetch_optional(&state.pool) // Ejecuta la consulta y obtiene un resultado opcional
         .await
-        .unwrap()
-        {
-            Some(user) => Some(user),
-            None => None,
-        }
     }
-    pub async fn save_user(State(state): State<AppState>, user: User) {
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            r#"
+        SELECT id, name, rolename, email, password, disabled_at, totp_secret, totp_enabled_at, created_at, updated_at
+        FROM users
+        WHERE id = $1
+        "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+    pub async fn save_user(State(state): State<AppState>, user: User) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
-        INSERT INTO users (name, rolename, email, password, created_at, updated_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO users (id, name, rolename, email, password, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
         "#,
         )
+        .bind(user.id)
         .bind(&user.name)
         .bind(&user.rolename)
         .bind(&user.email)
         .bind(&user.password)
-        .bind(&user.created_at)
-        .bind(&user.updated_at)
+        .bind(user.created_at)
+        .bind(user.updated_at)
         .execute(&state.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_all(pool: &PgPool) -> Result<Vec<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            r#"
+        SELECT id, name, rolename, email, password, disabled_at, totp_secret, totp_enabled_at, created_at, updated_at
+        FROM users
+        ORDER BY name
+        "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    // Usuario invitado: todavía no tiene contraseña y no puede iniciar sesión
+    pub async fn create_invited(
+        conn: &mut PgConnection,
+        name: &str,
+        email: &str,
+        rolename: &str,
+    ) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            r#"
+        INSERT INTO users (name, rolename, email, password)
+        VALUES ($1, $2, $3, '')
+        RETURNING id, name, rolename, email, password, disabled_at, totp_secret, totp_enabled_at, created_at, updated_at
+        "#,
+        )
+        .bind(name)
+        .bind(rolename)
+        .bind(email)
+        .fetch_one(conn)
         .await
-        .unwrap();
+    }
+
+    pub async fn update_role(
+        pool: &PgPool,
+        id: Uuid,
+        rolename: &str,
+    ) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            r#"
+        UPDATE users
+        SET rolename = $1, updated_at = current_timestamp
+        WHERE id = $2
+        RETURNING id, name, rolename, email, password, disabled_at, totp_secret, totp_enabled_at, created_at, updated_at
+        "#,
+        )
+        .bind(rolename)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn set_disabled(
+        conn: &mut PgConnection,
+        id: Uuid,
+        disabled: bool,
+    ) -> Result<Option<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            r#"
+        UPDATE users
+        SET disabled_at = CASE WHEN $1 THEN coalesce(disabled_at, current_timestamp) END,
+            updated_at = current_timestamp
+        WHERE id = $2
+        RETURNING id, name, rolename, email, password, disabled_at, totp_secret, totp_enabled_at, created_at, updated_at
+        "#,
+        )
+        .bind(disabled)
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+    }
+
+    // Una cadena vacía deja la cuenta sin contraseña válida
+    pub async fn update_password(
+        conn: &mut PgConnection,
+        id: Uuid,
+        password: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+        UPDATE users
+        SET password = $1, updated_at = current_timestamp
+        WHERE id = $2
+        "#,
+        )
+        .bind(password)
+        .bind(id)
+        .execute(conn)
+        .await?;
+        Ok(())
     }
 }
 
+pub mod activity;
+pub mod due;
+pub mod employee;
+pub mod login_attempt;
+pub mod password_token;
+pub mod payroll;
+pub mod refresh_token;
+pub mod rent;
+pub mod role;
+pub mod schedule;
 pub mod space;
+pub mod two_factor;
@@ -0,0 +1,81 @@
+use chrono::NaiveDateTime;
+use sqlx::{prelude::FromRow, PgConnection};
+use uuid::Uuid;
+
+pub struct PasswordTokenRepository;
+
+#[derive(Debug, FromRow)]
+pub struct PasswordToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+}
+
+impl PasswordTokenRepository {
+    // Un token nuevo invalida los anteriores del mismo usuario
+    pub async fn create(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        token_hash: &str,
+        purpose: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE password_tokens
+            SET used_at = current_timestamp
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO password_tokens (user_id, token_hash, purpose, expires_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(purpose)
+        .bind(expires_at)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_hash_for_update(
+        conn: &mut PgConnection,
+        token_hash: &str,
+    ) -> Result<Option<PasswordToken>, sqlx::Error> {
+        sqlx::query_as::<_, PasswordToken>(
+            r#"
+            SELECT id, user_id, purpose, expires_at, used_at
+            FROM password_tokens
+            WHERE token_hash = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(conn)
+        .await
+    }
+
+    pub async fn mark_used(conn: &mut PgConnection, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE password_tokens
+            SET used_at = current_timestamp
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::models::employee::{EmployeePayment, PayrollLine, PayrollTotal};
+
+pub struct PayrollRepository;
+
+impl PayrollRepository {
+    // Genera el pago del mes para cada funcionario activo con sueldo que todavía no lo tenga
+    pub async fn payroll_run(
+        pool: &PgPool,
+        month: i32,
+        year: i32,
+    ) -> Result<Vec<EmployeePayment>, sqlx::Error> {
+        sqlx::query_as::<_, EmployeePayment>(
+            r#"
+            INSERT INTO employees_payments (employee_id, amount, month, year)
+            SELECT id, salary, $1, $2
+            FROM employees
+            WHERE is_active AND salary IS NOT NULL
+            ON CONFLICT (employee_id, month, year) DO NOTHING
+            RETURNING id, employee_id, amount, payment_date, month, year, is_payed, created_at, updated_at
+            "#,
+        )
+        .bind(month)
+        .bind(year)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Funcionarios activos a los que no se les puede liquidar por no tener sueldo cargado
+    pub async fn find_without_salary(pool: &PgPool) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT id
+            FROM employees
+            WHERE is_active AND salary IS NULL
+            ORDER BY lastname, name
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn mark_payed(
+        pool: &PgPool,
+        id: Uuid,
+        payment_date: NaiveDate,
+    ) -> Result<Option<EmployeePayment>, sqlx::Error> {
+        sqlx::query_as::<_, EmployeePayment>(
+            r#"
+            UPDATE employees_payments
+            SET is_payed = true, payment_date = $1, updated_at = current_timestamp
+            WHERE id = $2 AND NOT is_payed
+            RETURNING id, employee_id, amount, payment_date, month, year, is_payed, created_at, updated_at
+            "#,
+        )
+        .bind(payment_date)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn find_by_employee(
+        pool: &PgPool,
+        employee_id: Uuid,
+    ) -> Result<Vec<EmployeePayment>, sqlx::Error> {
+        sqlx::query_as::<_, EmployeePayment>(
+            r#"
+            SELECT id, employee_id, amount, payment_date, month, year, is_payed, created_at, updated_at
+            FROM employees_payments
+            WHERE employee_id = $1
+            ORDER BY year DESC, month DESC
+            "#,
+        )
+        .bind(employee_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_period(
+        pool: &PgPool,
+        month: i32,
+        year: i32,
+    ) -> Result<Vec<PayrollLine>, sqlx::Error> {
+        sqlx::query_as::<_, PayrollLine>(
+            r#"
+            SELECT p.id, p.employee_id, e.name, e.lastname, e.ci, p.amount, p.payment_date, p.is_payed
+            FROM employees_payments p
+            INNER JOIN employees e ON e.id = p.employee_id
+            WHERE p.month = $1 AND p.year = $2
+            ORDER BY e.lastname, e.name
+            "#,
+        )
+        .bind(month)
+        .bind(year)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn total_by_period(
+        pool: &PgPool,
+        month: i32,
+        year: i32,
+    ) -> Result<PayrollTotal, sqlx::Error> {
+        sqlx::query_as::<_, PayrollTotal>(
+            r#"
+            SELECT COUNT(*) AS employees,
+                   COALESCE(SUM(amount), 0) AS total,
+                   COALESCE(SUM(amount) FILTER (WHERE is_payed), 0) AS paid,
+                   COALESCE(SUM(amount) FILTER (WHERE NOT is_payed), 0) AS pending
+            FROM employees_payments
+            WHERE month = $1 AND year = $2
+            "#,
+        )
+        .bind(month)
+        .bind(year)
+        .fetch_one(pool)
+        .await
+    }
+}
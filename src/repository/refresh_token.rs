@@ -0,0 +1,102 @@
+use chrono::NaiveDateTime;
+use sqlx::{prelude::FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+pub struct RefreshTokenRepository;
+
+#[derive(Debug, FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub expires_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl RefreshTokenRepository {
+    pub async fn create(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<Uuid, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING id
+            "#,
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .fetch_one(conn)
+        .await
+    }
+
+    pub async fn find_by_hash_for_update(
+        conn: &mut PgConnection,
+        token_hash: &str,
+    ) -> Result<Option<RefreshToken>, sqlx::Error> {
+        sqlx::query_as::<_, RefreshToken>(
+            r#"
+            SELECT id, user_id, expires_at, revoked_at
+            FROM refresh_tokens
+            WHERE token_hash = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(conn)
+        .await
+    }
+
+    pub async fn revoke(
+        conn: &mut PgConnection,
+        id: Uuid,
+        replaced_by: Option<Uuid>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = current_timestamp, replaced_by = $1, updated_at = current_timestamp
+            WHERE id = $2 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(replaced_by)
+        .bind(id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn revoke_by_hash(pool: &PgPool, token_hash: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = current_timestamp, updated_at = current_timestamp
+            WHERE token_hash = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(token_hash)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn revoke_all_for_user(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = current_timestamp, updated_at = current_timestamp
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
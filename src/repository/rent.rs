@@ -0,0 +1,258 @@
+use chrono::{NaiveDate, NaiveTime};
+use futures_util::stream::BoxStream;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::{
+    rent::{Rent, RentExportRow},
+    schedule::Weekday,
+};
+
+pub struct RentRepository;
+
+// Filtros del listado de alquileres ($1 espacio, $2 desde, $3 hasta, $4 incluir cancelados);
+// la exportación usa los mismos
+const RENT_FILTER_SQL: &str = r#"
+    ($1::uuid IS NULL OR r.space_id = $1)
+    AND ($2::date IS NULL OR r.rent_date >= $2)
+    AND ($3::date IS NULL OR r.rent_date <= $3)
+    AND ($4 OR r.cancelled_at IS NULL)
+"#;
+
+pub struct NewRent {
+    pub full_name: String,
+    pub phone: String,
+    pub space_id: Uuid,
+    pub rent_date: NaiveDate,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+    pub cost: Decimal,
+    pub is_payed: bool,
+    pub payment_date: Option<NaiveDate>,
+}
+
+impl RentRepository {
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<Rent>, sqlx::Error> {
+        sqlx::query_as::<_, Rent>(
+            r#"
+            SELECT id, full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date, cancelled_at, created_at, updated_at
+            FROM rents
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn find_all(
+        pool: &PgPool,
+        space_id: Option<Uuid>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        include_cancelled: bool,
+    ) -> Result<Vec<Rent>, sqlx::Error> {
+        let query = format!(
+            r#"
+            SELECT r.id, r.full_name, r.phone, r.space_id, r.rent_date, r.start_time, r.end_time, r.cost, r.is_payed, r.payment_date, r.cancelled_at, r.created_at, r.updated_at
+            FROM rents r
+            WHERE {}
+            ORDER BY r.rent_date, r.start_time
+            "#,
+            RENT_FILTER_SQL
+        );
+        sqlx::query_as::<_, Rent>(&query)
+            .bind(space_id)
+            .bind(from)
+            .bind(to)
+            .bind(include_cancelled)
+            .fetch_all(pool)
+            .await
+    }
+
+    // Mismos filtros que find_all, con el nombre del espacio y fila a fila para exportar.
+    // La consulta se arma afuera porque el stream la toma prestada
+    pub fn export_sql() -> String {
+        format!(
+            r#"
+            SELECT r.rent_date, r.start_time, r.end_time, s.name AS space_name, r.full_name, r.phone,
+                   r.cost, r.is_payed, r.payment_date, r.cancelled_at
+            FROM rents r
+            INNER JOIN space s ON s.id = r.space_id
+            WHERE {}
+            ORDER BY r.rent_date, r.start_time
+            "#,
+            RENT_FILTER_SQL
+        )
+    }
+
+    pub fn stream_export<'a>(
+        pool: &'a PgPool,
+        query: &'a str,
+        space_id: Option<Uuid>,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+        include_cancelled: bool,
+    ) -> BoxStream<'a, Result<RentExportRow, sqlx::Error>> {
+        sqlx::query_as::<_, RentExportRow>(query)
+            .bind(space_id)
+            .bind(from)
+            .bind(to)
+            .bind(include_cancelled)
+            .fetch(pool)
+    }
+
+    // Alquileres vigentes del espacio desde esa fecha, para controlar un cambio de horario
+    pub async fn find_upcoming_by_space(
+        conn: &mut PgConnection,
+        space_id: Uuid,
+        since: NaiveDate,
+    ) -> Result<Vec<Rent>, sqlx::Error> {
+        sqlx::query_as::<_, Rent>(
+            r#"
+            SELECT id, full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date, cancelled_at, created_at, updated_at
+            FROM rents
+            WHERE space_id = $1 AND rent_date >= $2 AND cancelled_at IS NULL
+            ORDER BY rent_date, start_time
+            "#,
+        )
+        .bind(space_id)
+        .bind(since)
+        .fetch_all(conn)
+        .await
+    }
+
+    // Alquileres vigentes del espacio que se superponen con la franja de ese día
+    pub async fn find_overlapping(
+        conn: &mut PgConnection,
+        space_id: Uuid,
+        rent_date: NaiveDate,
+        start_time: NaiveTime,
+        end_time: NaiveTime,
+        exclude_id: Option<Uuid>,
+    ) -> Result<Vec<Rent>, sqlx::Error> {
+        sqlx::query_as::<_, Rent>(
+            r#"
+            SELECT id, full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date, cancelled_at, created_at, updated_at
+            FROM rents
+            WHERE space_id = $1
+              AND rent_date = $2
+              AND start_time < $4
+              AND end_time > $3
+              AND cancelled_at IS NULL
+              AND ($5::uuid IS NULL OR id <> $5)
+            ORDER BY start_time
+            "#,
+        )
+        .bind(space_id)
+        .bind(rent_date)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(exclude_id)
+        .fetch_all(conn)
+        .await
+    }
+
+    // Alquileres futuros que caen en ese día de la semana, para validar horarios recurrentes
+    pub async fn find_overlapping_on_weekday(
+        conn: &mut PgConnection,
+        space_id: Uuid,
+        day: Weekday,
+        start_time: NaiveTime,
+        end_time: NaiveTime,
+        since: NaiveDate,
+    ) -> Result<Vec<Rent>, sqlx::Error> {
+        sqlx::query_as::<_, Rent>(
+            r#"
+            SELECT id, full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date, cancelled_at, created_at, updated_at
+            FROM rents
+            WHERE space_id = $1
+              AND EXTRACT(ISODOW FROM rent_date) = $2
+              AND start_time < $4
+              AND end_time > $3
+              AND rent_date >= $5
+              AND cancelled_at IS NULL
+            ORDER BY rent_date, start_time
+            "#,
+        )
+        .bind(space_id)
+        .bind(day as i32 + 1)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(since)
+        .fetch_all(conn)
+        .await
+    }
+
+    // Nombre y tarifa por hora del espacio, None si no existe o fue dado de baja
+    pub async fn find_space_rate(
+        pool: &PgPool,
+        space_id: Uuid,
+    ) -> Result<Option<(String, Option<Decimal>)>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT name, hourly_price
+            FROM space
+            WHERE id = $1 AND retired_at IS NULL
+            "#,
+        )
+        .bind(space_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn create(conn: &mut PgConnection, rent: NewRent) -> Result<Rent, sqlx::Error> {
+        sqlx::query_as::<_, Rent>(
+            r#"
+            INSERT INTO rents (full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id, full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date, cancelled_at, created_at, updated_at
+            "#,
+        )
+        .bind(&rent.full_name)
+        .bind(&rent.phone)
+        .bind(rent.space_id)
+        .bind(rent.rent_date)
+        .bind(rent.start_time)
+        .bind(rent.end_time)
+        .bind(rent.cost)
+        .bind(rent.is_payed)
+        .bind(rent.payment_date)
+        .fetch_one(conn)
+        .await
+    }
+
+    pub async fn cancel(pool: &PgPool, id: Uuid) -> Result<Option<Rent>, sqlx::Error> {
+        sqlx::query_as::<_, Rent>(
+            r#"
+            UPDATE rents
+            SET cancelled_at = current_timestamp, updated_at = current_timestamp
+            WHERE id = $1 AND cancelled_at IS NULL
+            RETURNING id, full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date, cancelled_at, created_at, updated_at
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn mark_payed(
+        pool: &PgPool,
+        id: Uuid,
+        payment_date: NaiveDate,
+    ) -> Result<Option<Rent>, sqlx::Error> {
+        sqlx::query_as::<_, Rent>(
+            r#"
+            UPDATE rents
+            SET is_payed = true, payment_date = $1, updated_at = current_timestamp
+            WHERE id = $2 AND NOT is_payed AND cancelled_at IS NULL
+            RETURNING id, full_name, phone, space_id, rent_date, start_time, end_time, cost, is_payed, payment_date, cancelled_at, created_at, updated_at
+            "#,
+        )
+        .bind(payment_date)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use sqlx::{prelude::FromRow, PgPool};
+
+pub struct RoleRepository;
+
+#[derive(Debug, FromRow)]
+pub struct RoleAccess {
+    pub has_permission: bool,
+    pub require_two_factor: bool,
+}
+
+#[derive(Debug, FromRow, Serialize)]
+pub struct Role {
+    pub name: String,
+    pub description: String,
+    pub require_two_factor: bool,
+    pub permissions: Vec<String>,
+}
+
+impl RoleRepository {
+    // Si el rol tiene el permiso y si exige verificación en dos pasos
+    pub async fn find_access(
+        pool: &PgPool,
+        role_name: &str,
+        permission: &str,
+    ) -> Result<Option<RoleAccess>, sqlx::Error> {
+        sqlx::query_as::<_, RoleAccess>(
+            r#"
+            SELECT
+                EXISTS (
+                    SELECT 1
+                    FROM role_permissions rp
+                    WHERE rp.role_name = r.name AND rp.permission_name = $2
+                ) AS has_permission,
+                r.require_two_factor
+            FROM roles r
+            WHERE r.name = $1
+            "#,
+        )
+        .bind(role_name)
+        .bind(permission)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn requires_two_factor(pool: &PgPool, role_name: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT coalesce(
+                (SELECT require_two_factor FROM roles WHERE name = $1),
+                false
+            )
+            "#,
+        )
+        .bind(role_name)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn find_all(pool: &PgPool) -> Result<Vec<Role>, sqlx::Error> {
+        sqlx::query_as::<_, Role>(
+            r#"
+            SELECT
+                r.name,
+                r.description,
+                r.require_two_factor,
+                coalesce(
+                    array_agg(rp.permission_name ORDER BY rp.permission_name)
+                        FILTER (WHERE rp.permission_name IS NOT NULL),
+                    '{}'
+                ) AS permissions
+            FROM roles r
+            LEFT JOIN role_permissions rp ON rp.role_name = r.name
+            GROUP BY r.name
+            ORDER BY r.name
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn set_require_two_factor(
+        pool: &PgPool,
+        role_name: &str,
+        required: bool,
+    ) -> Result<bool, sqlx::Error> {
+        let updated = sqlx::query(
+            r#"
+            UPDATE roles
+            SET require_two_factor = $1, updated_at = current_timestamp
+            WHERE name = $2
+            "#,
+        )
+        .bind(required)
+        .bind(role_name)
+        .execute(pool)
+        .await?;
+        Ok(updated.rows_affected() == 1)
+    }
+}
@@ -0,0 +1,151 @@
+use chrono::NaiveTime;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::models::schedule::{ScheduleSlot, ScheduleSlotDetail, Weekday};
+
+pub struct ScheduleRepository;
+
+impl ScheduleRepository {
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<ScheduleSlot>, sqlx::Error> {
+        sqlx::query_as::<_, ScheduleSlot>(
+            r#"
+            SELECT id, activity_id, day, start_time, end_time, space_id, created_at, updated_at
+            FROM activities_schedule
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    // Horarios de actividades vigentes, ordenados por día, espacio y hora
+    pub async fn find_all_detailed(pool: &PgPool) -> Result<Vec<ScheduleSlotDetail>, sqlx::Error> {
+        sqlx::query_as::<_, ScheduleSlotDetail>(
+            r#"
+            SELECT s.id, s.activity_id, a.name AS activity_name, s.day, s.start_time, s.end_time,
+                   s.space_id, sp.name AS space_name
+            FROM activities_schedule s
+            INNER JOIN activities a ON a.id = s.activity_id
+            INNER JOIN space sp ON sp.id = s.space_id
+            WHERE a.retired_at IS NULL
+            ORDER BY s.day, sp.name, s.start_time
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_space(
+        conn: &mut PgConnection,
+        space_id: Uuid,
+    ) -> Result<Vec<ScheduleSlotDetail>, sqlx::Error> {
+        sqlx::query_as::<_, ScheduleSlotDetail>(
+            r#"
+            SELECT s.id, s.activity_id, a.name AS activity_name, s.day, s.start_time, s.end_time,
+                   s.space_id, sp.name AS space_name
+            FROM activities_schedule s
+            INNER JOIN activities a ON a.id = s.activity_id
+            INNER JOIN space sp ON sp.id = s.space_id
+            WHERE s.space_id = $1 AND a.retired_at IS NULL
+            ORDER BY s.day, s.start_time
+            "#,
+        )
+        .bind(space_id)
+        .fetch_all(conn)
+        .await
+    }
+
+    // Horarios de actividades que se superponen con la franja en el mismo espacio
+    pub async fn find_overlapping_slots(
+        conn: &mut PgConnection,
+        space_id: Uuid,
+        day: Weekday,
+        start_time: NaiveTime,
+        end_time: NaiveTime,
+        exclude_id: Option<Uuid>,
+    ) -> Result<Vec<ScheduleSlotDetail>, sqlx::Error> {
+        sqlx::query_as::<_, ScheduleSlotDetail>(
+            r#"
+            SELECT s.id, s.activity_id, a.name AS activity_name, s.day, s.start_time, s.end_time,
+                   s.space_id, sp.name AS space_name
+            FROM activities_schedule s
+            INNER JOIN activities a ON a.id = s.activity_id
+            INNER JOIN space sp ON sp.id = s.space_id
+            WHERE s.space_id = $1
+              AND s.day = $2
+              AND s.start_time < $4
+              AND s.end_time > $3
+              AND a.retired_at IS NULL
+              AND ($5::uuid IS NULL OR s.id <> $5)
+            ORDER BY s.start_time
+            "#,
+        )
+        .bind(space_id)
+        .bind(day)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(exclude_id)
+        .fetch_all(conn)
+        .await
+    }
+
+    pub async fn create(
+        conn: &mut PgConnection,
+        activity_id: Uuid,
+        day: Weekday,
+        start_time: NaiveTime,
+        end_time: NaiveTime,
+        space_id: Uuid,
+    ) -> Result<ScheduleSlot, sqlx::Error> {
+        sqlx::query_as::<_, ScheduleSlot>(
+            r#"
+            INSERT INTO activities_schedule (activity_id, day, start_time, end_time, space_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, activity_id, day, start_time, end_time, space_id, created_at, updated_at
+            "#,
+        )
+        .bind(activity_id)
+        .bind(day)
+        .bind(start_time)
+        .bind(end_time)
+        .bind(space_id)
+        .fetch_one(conn)
+        .await
+    }
+
+    pub async fn update(
+        conn: &mut PgConnection,
+        slot: &ScheduleSlot,
+    ) -> Result<ScheduleSlot, sqlx::Error> {
+        sqlx::query_as::<_, ScheduleSlot>(
+            r#"
+            UPDATE activities_schedule
+            SET day = $1, start_time = $2, end_time = $3, space_id = $4, updated_at = current_timestamp
+            WHERE id = $5
+            RETURNING id, activity_id, day, start_time, end_time, space_id, created_at, updated_at
+            "#,
+        )
+        .bind(slot.day)
+        .bind(slot.start_time)
+        .bind(slot.end_time)
+        .bind(slot.space_id)
+        .bind(slot.id)
+        .fetch_one(conn)
+        .await
+    }
+
+    pub async fn delete(pool: &PgPool, id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM activities_schedule
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -1,31 +1,55 @@
-use sqlx::PgPool;
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
 
-use crate::models::space;
+use crate::models::space::{self, OpeningHours};
 
 pub struct CreateSpaceRepository;
 
+pub struct SpaceData {
+    pub name: String,
+    pub capacity: Option<i32>,
+    pub is_indoor: bool,
+    pub hourly_price: Option<Decimal>,
+}
+
 impl CreateSpaceRepository {
-    pub async fn create(pool: &PgPool, name: &str) -> Result<space::Space, sqlx::Error> {
+    pub async fn create(
+        pool: &PgPool,
+        data: &SpaceData,
+        opening_hours: &[OpeningHours],
+    ) -> Result<space::Space, sqlx::Error> {
+        let mut tx = pool.begin().await?;
         let space = sqlx::query_as::<_, space::Space>(
             r#"
-        INSERT INTO space (name, created_at, updated_at)
-        VALUES ($1, $2, $3)
-        RETURNING id, name, created_at, updated_at
+        INSERT INTO space (name, capacity, is_indoor, hourly_price, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, name, capacity, is_indoor, hourly_price, retired_at, created_at, updated_at
         "#,
         )
-        .bind(name)
+        .bind(&data.name)
+        .bind(data.capacity)
+        .bind(data.is_indoor)
+        .bind(data.hourly_price)
         .bind(chrono::Local::now().naive_local())
         .bind(chrono::Local::now().naive_local())
-        .fetch_one(pool)
+        .fetch_one(&mut *tx)
         .await?;
 
+        Self::insert_opening_hours(&mut tx, space.id, opening_hours).await?;
+        tx.commit().await?;
+
         Ok(space)
     }
 
-    pub async fn find_by_name(pool: &PgPool, name: &str) -> Option<space::Space> {
-        let query = sqlx::query_as(
+    pub async fn find_by_name(
+        pool: &PgPool,
+        name: &str,
+    ) -> Result<Option<space::Space>, sqlx::Error> {
+        sqlx::query_as(
             r#"
-        SELECT id, name, created_at, updated_at
+        SELECT id, name, capacity, is_indoor, hourly_price, retired_at, created_at, updated_at
         FROM space
         WHERE name = $1
         "#,
@@ -33,10 +57,171 @@ impl CreateSpaceRepository {
         .bind(name) // Bind del parámetro name
         .fetch_optional(pool) // Ejecuta la consulta y obtiene un resultado opcional
         .await
-        .unwrap();
-        match query {
-            Some(space) => Some(space),
-            None => None,
+    }
+
+    pub async fn find_by_id(pool: &PgPool, id: Uuid) -> Result<Option<space::Space>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT id, name, capacity, is_indoor, hourly_price, retired_at, created_at, updated_at
+        FROM space
+        WHERE id = $1
+        "#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    // Igual que find_by_id, pero bloquea el espacio hasta el fin de la transacción: los alquileres
+    // y horarios del mismo espacio se validan y guardan de a uno, así dos pedidos simultáneos no
+    // toman la misma franja
+    pub async fn find_by_id_for_update(
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<space::Space>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT id, name, capacity, is_indoor, hourly_price, retired_at, created_at, updated_at
+        FROM space
+        WHERE id = $1
+        FOR UPDATE
+        "#,
+        )
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+    }
+
+    pub async fn find_all(
+        pool: &PgPool,
+        include_retired: bool,
+    ) -> Result<Vec<space::Space>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT id, name, capacity, is_indoor, hourly_price, retired_at, created_at, updated_at
+        FROM space
+        WHERE $1 OR retired_at IS NULL
+        ORDER BY name
+        "#,
+        )
+        .bind(include_retired)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_opening_hours(
+        pool: &PgPool,
+        space_id: Uuid,
+    ) -> Result<Vec<OpeningHours>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT day, opens_at, closes_at
+        FROM space_opening_hours
+        WHERE space_id = $1
+        ORDER BY day
+        "#,
+        )
+        .bind(space_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    // Actualiza los datos del espacio; si se indica `opening_hours` reemplaza el horario completo
+    pub async fn update(
+        conn: &mut PgConnection,
+        id: Uuid,
+        data: &SpaceData,
+        opening_hours: Option<&[OpeningHours]>,
+    ) -> Result<space::Space, sqlx::Error> {
+        let space = sqlx::query_as::<_, space::Space>(
+            r#"
+        UPDATE space
+        SET name = $1, capacity = $2, is_indoor = $3, hourly_price = $4, updated_at = $5
+        WHERE id = $6
+        RETURNING id, name, capacity, is_indoor, hourly_price, retired_at, created_at, updated_at
+        "#,
+        )
+        .bind(&data.name)
+        .bind(data.capacity)
+        .bind(data.is_indoor)
+        .bind(data.hourly_price)
+        .bind(chrono::Local::now().naive_local())
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if let Some(opening_hours) = opening_hours {
+            sqlx::query("DELETE FROM space_opening_hours WHERE space_id = $1")
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+            Self::insert_opening_hours(conn, id, opening_hours).await?;
+        }
+
+        Ok(space)
+    }
+
+    // Cantidad de horarios de actividades vigentes y de alquileres futuros que usan el espacio
+    pub async fn count_references(
+        conn: &mut PgConnection,
+        id: Uuid,
+        since: NaiveDate,
+    ) -> Result<(i64, i64), sqlx::Error> {
+        sqlx::query_as(
+            r#"
+        SELECT
+            (SELECT COUNT(*)
+             FROM activities_schedule s
+             INNER JOIN activities a ON a.id = s.activity_id
+             WHERE s.space_id = $1 AND a.retired_at IS NULL),
+            (SELECT COUNT(*)
+             FROM rents
+             WHERE space_id = $1 AND rent_date >= $2 AND cancelled_at IS NULL)
+        "#,
+        )
+        .bind(id)
+        .bind(since)
+        .fetch_one(conn)
+        .await
+    }
+
+    pub async fn retire(
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<Option<space::Space>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+        UPDATE space
+        SET retired_at = $1, updated_at = $1
+        WHERE id = $2 AND retired_at IS NULL
+        RETURNING id, name, capacity, is_indoor, hourly_price, retired_at, created_at, updated_at
+        "#,
+        )
+        .bind(chrono::Local::now().naive_local())
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+    }
+
+    async fn insert_opening_hours(
+        conn: &mut sqlx::PgConnection,
+        space_id: Uuid,
+        opening_hours: &[OpeningHours],
+    ) -> Result<(), sqlx::Error> {
+        for hours in opening_hours {
+            sqlx::query(
+                r#"
+        INSERT INTO space_opening_hours (space_id, day, opens_at, closes_at)
+        VALUES ($1, $2, $3, $4)
+        "#,
+            )
+            .bind(space_id)
+            .bind(hours.day)
+            .bind(hours.opens_at)
+            .bind(hours.closes_at)
+            .execute(&mut *conn)
+            .await?;
         }
+        Ok(())
     }
 }
@@ -0,0 +1,199 @@
+use chrono::NaiveDateTime;
+use sqlx::{prelude::FromRow, PgConnection, PgPool};
+use uuid::Uuid;
+
+pub struct TwoFactorRepository;
+
+#[derive(Debug, FromRow)]
+pub struct TwoFactorChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub failed_attempts: i32,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+}
+
+impl TwoFactorRepository {
+    // El secreto queda pendiente hasta que el usuario lo confirme con un código
+    pub async fn set_pending_secret(
+        pool: &PgPool,
+        user_id: Uuid,
+        secret: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_secret = $1, totp_enabled_at = NULL, totp_last_step = NULL, updated_at = current_timestamp
+            WHERE id = $2
+            "#,
+        )
+        .bind(secret)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    // Registra el intervalo del código TOTP aceptado. Devuelve false si ya se usó ese intervalo
+    // o uno posterior, así un código visto por otro no se puede volver a usar
+    pub async fn accept_step(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        step: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_last_step = $2
+            WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(conn)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    // Activa el segundo factor y reemplaza los códigos de recuperación
+    pub async fn enable(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        recovery_code_hashes: &[String],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_enabled_at = current_timestamp, updated_at = current_timestamp
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO recovery_codes (user_id, code_hash)
+            SELECT $1, unnest($2::varchar[])
+            "#,
+        )
+        .bind(user_id)
+        .bind(recovery_code_hashes)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn disable(conn: &mut PgConnection, user_id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_step = NULL, updated_at = current_timestamp
+            WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("DELETE FROM recovery_codes WHERE user_id = $1")
+            .bind(user_id)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+
+    // Marca el código como usado; devuelve false si no existe o ya se usó
+    pub async fn use_recovery_code(
+        conn: &mut PgConnection,
+        user_id: Uuid,
+        code_hash: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let used = sqlx::query(
+            r#"
+            UPDATE recovery_codes
+            SET used_at = current_timestamp
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(conn)
+        .await?;
+        Ok(used.rows_affected() == 1)
+    }
+
+    pub async fn create_challenge(
+        pool: &PgPool,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO two_factor_challenges (user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_challenge_for_update(
+        conn: &mut PgConnection,
+        token_hash: &str,
+    ) -> Result<Option<TwoFactorChallenge>, sqlx::Error> {
+        sqlx::query_as::<_, TwoFactorChallenge>(
+            r#"
+            SELECT id, user_id, failed_attempts, expires_at, used_at
+            FROM two_factor_challenges
+            WHERE token_hash = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(conn)
+        .await
+    }
+
+    pub async fn record_challenge_failure(
+        conn: &mut PgConnection,
+        id: Uuid,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE two_factor_challenges
+            SET failed_attempts = failed_attempts + 1
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn mark_challenge_used(conn: &mut PgConnection, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE two_factor_challenges
+            SET used_at = current_timestamp
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
@@ -2,6 +2,9 @@ use jsonwebtoken::{DecodingKey, EncodingKey};
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::mail::Mailer;
 
 // implement a method to create a response type containing the JWT
 impl AuthBody {
@@ -36,10 +39,16 @@ pub struct AuthRequestPayload {
 
 #[derive(Debug, FromRow, Clone, Deserialize, Serialize)]
 pub struct User {
+    pub id: Uuid,
     pub name: String,
     pub rolename: String,
     pub email: String,
+    #[serde(skip_serializing)]
     pub password: String,
+    pub disabled_at: Option<chrono::NaiveDateTime>,
+    #[serde(skip_serializing)]
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<chrono::NaiveDateTime>,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
 }
@@ -48,6 +57,10 @@ pub struct User {
 pub struct AppState {
     pub pool: sqlx::PgPool,
     pub jwt_secret: Keys,
+    pub allow_registration: bool,
+    pub trusted_proxy_hops: usize,
+    pub frontend_url: String,
+    pub mailer: Arc<dyn Mailer>,
 }
 
 #[derive(Clone)]